@@ -4,6 +4,7 @@
 
 use std::ffi::{c_char, CString};
 use std::sync::Mutex;
+use crate::core::types::{HttpStatus, ResponseContext};
 
 /// 统一的错误类型定义
 #[derive(Debug, Clone)]
@@ -12,7 +13,8 @@ pub enum HushError {
     HttpError(String),
     RouteNotFound,
     MethodNotAllowed,
-    
+    RequestTimeout,
+
     // 数据库相关错误
     DatabaseError(String),
     ConnectionFailed,
@@ -70,7 +72,7 @@ pub enum ErrorCode {
 impl From<HushError> for ErrorCode {
     fn from(error: HushError) -> Self {
         match error {
-            HushError::HttpError(_) | HushError::RouteNotFound | HushError::MethodNotAllowed => ErrorCode::HttpError,
+            HushError::HttpError(_) | HushError::RouteNotFound | HushError::MethodNotAllowed | HushError::RequestTimeout => ErrorCode::HttpError,
             HushError::DatabaseError(_) | HushError::ConnectionFailed | HushError::QueryFailed(_) | HushError::TransactionFailed => ErrorCode::DatabaseError,
             HushError::AuthenticationFailed | HushError::AuthorizationFailed | HushError::InvalidToken | HushError::TokenExpired => ErrorCode::AuthError,
             HushError::ConfigError(_) | HushError::ConfigNotFound(_) | HushError::ConfigParseError(_) => ErrorCode::ConfigError,
@@ -87,6 +89,7 @@ impl std::fmt::Display for HushError {
             HushError::HttpError(msg) => write!(f, "HTTP Error: {}", msg),
             HushError::RouteNotFound => write!(f, "Route not found"),
             HushError::MethodNotAllowed => write!(f, "Method not allowed"),
+            HushError::RequestTimeout => write!(f, "Request timeout"),
             HushError::DatabaseError(msg) => write!(f, "Database Error: {}", msg),
             HushError::ConnectionFailed => write!(f, "Database connection failed"),
             HushError::QueryFailed(msg) => write!(f, "Query failed: {}", msg),
@@ -115,6 +118,54 @@ impl std::fmt::Display for HushError {
 
 impl std::error::Error for HushError {}
 
+/// 将 `HushError` 映射到合适的 HTTP 状态码。这是 [`default_error_response`]
+/// 使用的表，也可以被自定义的错误处理函数复用，在其基础上只覆盖个别分支
+/// Maps a `HushError` to the appropriate HTTP status code. This is the table
+/// used by [`default_error_response`]; a custom error handler can reuse it
+/// and only override a handful of branches.
+pub fn error_status(error: &HushError) -> HttpStatus {
+    match error {
+        HushError::RouteNotFound | HushError::FileNotFound => HttpStatus::NotFound,
+        HushError::MethodNotAllowed => HttpStatus::MethodNotAllowed,
+        HushError::RequestTimeout | HushError::Timeout => HttpStatus::RequestTimeout,
+        HushError::AuthenticationFailed | HushError::InvalidToken | HushError::TokenExpired => HttpStatus::Unauthorized,
+        HushError::AuthorizationFailed | HushError::PermissionDenied => HttpStatus::Forbidden,
+        HushError::ValidationError(_) | HushError::InvalidInput(_) | HushError::InvalidParameter => HttpStatus::BadRequest,
+        _ => HttpStatus::InternalServerError,
+    }
+}
+
+/// 默认的"错误 -> 响应"转换：把任意 `HushError` 映射到 [`error_status`] 给出
+/// 的状态码，并生成统一的 JSON 错误信封 `{"error": "..."}`。在没有通过
+/// `WebServer::set_error_handler` 注册自定义转换函数时，这是链执行失败后的
+/// 兜底行为
+/// Default error-to-response mapping: converts any `HushError` into the
+/// status code given by [`error_status`] plus a uniform JSON error envelope
+/// `{"error": "..."}`. This is the fallback used when no custom function has
+/// been registered via `WebServer::set_error_handler`.
+pub fn default_error_response(error: &HushError) -> ResponseContext {
+    let body = format!(r#"{{"error": "{}"}}"#, json_escape(&error.to_string()));
+    ResponseContext::with_json(error_status(error), &body)
+}
+
+/// 转义字符串中的 JSON 特殊字符，不包含两端的引号
+/// Escape JSON special characters in a string, without the surrounding quotes
+fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
 /// 全局错误状态管理
 static LAST_ERROR: Mutex<Option<HushError>> = Mutex::new(None);
 
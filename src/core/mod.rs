@@ -2,13 +2,14 @@
 // Hush 框架核心模块 | Hush Framework Core Module
 // ============================================================================
 
+pub(crate) mod crypto;
 pub mod error;
 pub mod ffi;
 pub mod memory;
 pub mod types;
 
 // 重新导出核心类型和函数
-pub use error::{HushError, HushResult, ErrorCode};
+pub use error::{HushError, HushResult, ErrorCode, error_status, default_error_response};
 pub use ffi::{FFIResult, to_c_string, from_c_string, handle_ffi_result};
 pub use memory::{MemoryManager, CStringWrapper};
 pub use types::{RequestContext, ResponseContext};
\ No newline at end of file
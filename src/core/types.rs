@@ -53,11 +53,14 @@ pub enum HttpStatus {
     Ok = 200,
     Created = 201,
     NoContent = 204,
+    NotModified = 304,
     BadRequest = 400,
     Unauthorized = 401,
     Forbidden = 403,
     NotFound = 404,
     MethodNotAllowed = 405,
+    RequestTimeout = 408,
+    TooManyRequests = 429,
     InternalServerError = 500,
     NotImplemented = 501,
     ServiceUnavailable = 503,
@@ -67,17 +70,40 @@ impl HttpStatus {
     pub fn as_u16(&self) -> u16 {
         *self as u16
     }
-    
+
+    pub fn from_u16(code: u16) -> HushResult<Self> {
+        match code {
+            200 => Ok(HttpStatus::Ok),
+            201 => Ok(HttpStatus::Created),
+            204 => Ok(HttpStatus::NoContent),
+            304 => Ok(HttpStatus::NotModified),
+            400 => Ok(HttpStatus::BadRequest),
+            401 => Ok(HttpStatus::Unauthorized),
+            403 => Ok(HttpStatus::Forbidden),
+            404 => Ok(HttpStatus::NotFound),
+            405 => Ok(HttpStatus::MethodNotAllowed),
+            408 => Ok(HttpStatus::RequestTimeout),
+            429 => Ok(HttpStatus::TooManyRequests),
+            500 => Ok(HttpStatus::InternalServerError),
+            501 => Ok(HttpStatus::NotImplemented),
+            503 => Ok(HttpStatus::ServiceUnavailable),
+            _ => Err(HushError::InvalidInput(format!("Unknown HTTP status code: {}", code))),
+        }
+    }
+
     pub fn reason_phrase(&self) -> &'static str {
         match self {
             HttpStatus::Ok => "OK",
             HttpStatus::Created => "Created",
             HttpStatus::NoContent => "No Content",
+            HttpStatus::NotModified => "Not Modified",
             HttpStatus::BadRequest => "Bad Request",
             HttpStatus::Unauthorized => "Unauthorized",
             HttpStatus::Forbidden => "Forbidden",
             HttpStatus::NotFound => "Not Found",
             HttpStatus::MethodNotAllowed => "Method Not Allowed",
+            HttpStatus::RequestTimeout => "Request Timeout",
+            HttpStatus::TooManyRequests => "Too Many Requests",
             HttpStatus::InternalServerError => "Internal Server Error",
             HttpStatus::NotImplemented => "Not Implemented",
             HttpStatus::ServiceUnavailable => "Service Unavailable",
@@ -96,6 +122,9 @@ pub struct RequestContext {
     pub user_data: HashMap<String, String>,
     pub start_time: SystemTime,
     pub trace_id: String,
+    /// 动态路由段匹配出的路径参数（如 `/users/:id` 中的 `id`）
+    /// Path params extracted by dynamic route matching (e.g. `id` from `/users/:id`)
+    pub path_params: HashMap<String, String>,
 }
 
 impl RequestContext {
@@ -109,6 +138,7 @@ impl RequestContext {
             user_data: HashMap::new(),
             start_time: SystemTime::now(),
             trace_id: uuid::Uuid::new_v4().to_string(),
+            path_params: HashMap::new(),
         }
     }
     
@@ -135,23 +165,62 @@ impl RequestContext {
     pub fn get_query_param(&self, key: &str) -> Option<&String> {
         self.query_params.get(key)
     }
+
+    pub fn set_path_params(&mut self, params: HashMap<String, String>) {
+        self.path_params = params;
+    }
+
+    pub fn get_path_param(&self, key: &str) -> Option<&String> {
+        self.path_params.get(key)
+    }
     
     pub fn get_user_data(&self, key: &str) -> Option<&String> {
         self.user_data.get(key)
     }
-    
+
+    pub fn remove_user_data(&mut self, key: &str) -> Option<String> {
+        self.user_data.remove(key)
+    }
+
     pub fn body_as_string(&self) -> HushResult<String> {
         String::from_utf8(self.body.clone())
             .map_err(|_| HushError::InvalidInput("Invalid UTF-8 in request body".to_string()))
     }
 }
 
+/// 模板渲染上下文中的值：纯文本、用于 `{{#section}}` 重复渲染的子上下文列表，
+/// 或用于该区块是否渲染的布尔开关
+/// A value in a template-rendering context: plain text, a list of
+/// sub-contexts for `{{#section}}` repetition, or a boolean switch for
+/// whether that section renders at all
+#[derive(Debug, Clone)]
+pub enum TemplateValue {
+    Text(String),
+    List(Vec<HashMap<String, TemplateValue>>),
+    Bool(bool),
+}
+
+/// 一次尚未渲染的模板响应：处理器只声明了模板名和上下文，真正的文件加载
+/// 和占位符替换延迟到 [`super::super::web::template::TemplateEngine`]
+/// 按配置的模板目录完成
+/// A template response that hasn't been rendered yet: the handler only
+/// declared the template name and context; the actual file load and
+/// placeholder substitution is deferred to the registered template engine,
+/// which knows the configured templates directory
+#[derive(Debug, Clone)]
+pub struct PendingTemplate {
+    pub name: String,
+    pub context: HashMap<String, TemplateValue>,
+    pub base_override: Option<String>,
+}
+
 /// 响应上下文，包含所有响应相关信息
 #[derive(Debug, Clone)]
 pub struct ResponseContext {
     pub status: HttpStatus,
     pub headers: HashMap<String, String>,
     pub body: Vec<u8>,
+    pub(crate) pending_template: Option<PendingTemplate>,
 }
 
 impl ResponseContext {
@@ -160,35 +229,78 @@ impl ResponseContext {
             status,
             headers: HashMap::new(),
             body: Vec::new(),
+            pending_template: None,
         }
     }
-    
+
     pub fn with_body(status: HttpStatus, body: Vec<u8>) -> Self {
         Self {
             status,
             headers: HashMap::new(),
             body,
+            pending_template: None,
         }
     }
-    
+
     pub fn with_text(status: HttpStatus, text: &str) -> Self {
         Self {
             status,
             headers: HashMap::new(),
             body: text.as_bytes().to_vec(),
+            pending_template: None,
         }
     }
-    
+
     pub fn with_json(status: HttpStatus, json: &str) -> Self {
         let mut response = Self::with_text(status, json);
         response.add_header("Content-Type".to_string(), "application/json".to_string());
         response
     }
-    
+
+    /// 声明一个待渲染的模板响应：`name` 是相对于模板引擎基目录的文件名，
+    /// `context` 提供 `{{name}}` 占位符和 `{{#section}}` 区块的数据。实际渲染
+    /// 由已注册的 `TemplateEngine` 在响应流水线的最后阶段完成
+    /// Declare a pending template response: `name` is the file name relative
+    /// to the template engine's base directory, `context` supplies the data
+    /// for `{{name}}` placeholders and `{{#section}}` blocks. Actual
+    /// rendering happens in the registered `TemplateEngine` at the end of
+    /// the response pipeline
+    pub fn with_template(name: &str, context: HashMap<String, TemplateValue>) -> Self {
+        let mut response = Self::new(HttpStatus::Ok);
+        response.pending_template = Some(PendingTemplate {
+            name: name.to_string(),
+            context,
+            base_override: None,
+        });
+        response
+    }
+
+    /// 与 [`Self::with_template`] 相同，但使用 `base_dir` 覆盖模板引擎配置的
+    /// 基目录，用于需要从其他目录加载模板的场景
+    pub fn with_template_from(name: &str, context: HashMap<String, TemplateValue>, base_dir: &str) -> Self {
+        let mut response = Self::with_template(name, context);
+        if let Some(pending) = response.pending_template.as_mut() {
+            pending.base_override = Some(base_dir.to_string());
+        }
+        response
+    }
+
+    /// 取出尚未渲染的模板（如果有），供模板引擎消费
+    pub(crate) fn take_pending_template(&mut self) -> Option<PendingTemplate> {
+        self.pending_template.take()
+    }
+
     pub fn add_header(&mut self, key: String, value: String) {
         self.headers.insert(key, value);
     }
-    
+
+    /// 仅当该响应头尚未被设置时才插入，用于默认响应头这类“不覆盖已有值”的场景
+    /// Insert the header only if it isn't already set — for default-header
+    /// use cases that must not override a value the handler already set
+    pub fn set_header_if_absent(&mut self, key: String, value: String) {
+        self.headers.entry(key).or_insert(value);
+    }
+
     pub fn set_body(&mut self, body: Vec<u8>) {
         self.body = body;
     }
@@ -280,11 +392,14 @@ impl ResponseContext {
             200 => HttpStatus::Ok,
             201 => HttpStatus::Created,
             204 => HttpStatus::NoContent,
+            304 => HttpStatus::NotModified,
             400 => HttpStatus::BadRequest,
             401 => HttpStatus::Unauthorized,
             403 => HttpStatus::Forbidden,
             404 => HttpStatus::NotFound,
             405 => HttpStatus::MethodNotAllowed,
+            408 => HttpStatus::RequestTimeout,
+            429 => HttpStatus::TooManyRequests,
             500 => HttpStatus::InternalServerError,
             501 => HttpStatus::NotImplemented,
             503 => HttpStatus::ServiceUnavailable,
@@ -306,6 +421,7 @@ impl ResponseContext {
             status,
             headers: HashMap::new(), // TODO: 实现 headers 的转换
             body,
+            pending_template: None,
         })
     }
 }
\ No newline at end of file
@@ -2,15 +2,20 @@
 // 依赖导入 | Dependencies Import | 依存関係のインポート
 // ============================================================================
 
+mod core;
+
 // actix-web: 高性能的 Rust web 框架 | actix-web: High-performance Rust web framework | actix-web: 高性能なRust webフレームワーク
 use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer, web::Bytes};
 
 // 标准库导入 | Standard library imports | 標準ライブラリのインポート
 use std::collections::HashMap;           // 哈希映射，用于存储路由 | HashMap for storing routes | ルートを格納するためのHashMap
 use std::ffi::{c_char, CStr, CString};   // C 语言 FFI 类型 | C language FFI types | C言語FFI型
-use std::sync::{Arc, Mutex};             // 线程安全的共享数据结构 | Thread-safe shared data structures | スレッドセーフな共有データ構造
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering}; // 无锁状态标志与 id 分配 | Lock-free flags and id allocation | ロックフリーなフラグとID割り当て
+use std::sync::{Arc, Mutex, OnceLock};   // 线程安全的共享数据结构 | Thread-safe shared data structures | スレッドセーフな共有データ構造
 use std::thread;                         // 线程操作 | Thread operations | スレッド操作
 
+use crate::core::error::{HushError, HushResult, set_last_error};
+
 // ============================================================================
 // 示例函数：基本的 FFI 演示 | Example Function: Basic FFI Demonstration | サンプル関数：基本的なFFIデモンストレーション
 // ============================================================================
@@ -40,141 +45,664 @@ pub extern "C" fn rust_hello_world() -> *const c_char {
     hello.into_raw()
 }
 
+// ============================================================================
+// 路由 Trie 树 | Route Trie | ルートTrie木
+// ============================================================================
+//
+// 每个 HTTP 方法拥有一棵独立的 Trie，按 '/' 切分的路径段逐级匹配。
+// 每个节点保存字面量子节点（HashMap）、至多一个参数子节点（`{name}`）、
+// 以及至多一个尾部通配符子节点（`{name:*}`）。
+// Each HTTP method owns its own trie, matched segment-by-segment on '/'.
+// Each node stores literal children (HashMap), at most one param child
+// (`{name}`), and at most one trailing wildcard child (`{name:*}`).
+
+/// Zig 处理函数指针类型：method, path, query（原始查询字符串，如 "page=2&sort=asc"）,
+/// headers（"Name: Value\n..." 块）, body, params（"name=value&..." 形式）。
+/// 返回一个指向 `CHushResponse` 的指针，由 Zig 负责填充状态码、headers 和正文。
+/// Zig handler function pointer type: method, path, query (the raw query string, e.g.
+/// "page=2&sort=asc"), headers ("Name: Value\n..." block), body, params ("name=value&..." form).
+/// Returns a pointer to a `CHushResponse` filled in by Zig with status, headers and body.
+pub type RouteHandlerFn = extern "C" fn(
+    *const c_char,
+    *const c_char,
+    *const c_char,
+    *const c_char,
+    *const c_char,
+    *const c_char,
+) -> *mut CHushResponse;
+
+/// 路径段的解析结果 | Parsed kind of a single path segment
+enum Segment<'a> {
+    Literal(&'a str),
+    Param(&'a str),
+    Wildcard(&'a str),
+}
+
+fn parse_segment(segment: &str) -> Segment<'_> {
+    if let Some(inner) = segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+        if let Some(name) = inner.strip_suffix(":*") {
+            Segment::Wildcard(name)
+        } else {
+            Segment::Param(inner)
+        }
+    } else {
+        Segment::Literal(segment)
+    }
+}
+
+/// 单个方法的路由 Trie 节点 | A single route trie node for one HTTP method
+#[derive(Default)]
+struct RouteNode {
+    literal_children: HashMap<String, RouteNode>,
+    param_child: Option<(String, Box<RouteNode>)>,
+    wildcard_child: Option<(String, Box<RouteNode>)>,
+    handler: Option<RouteHandlerFn>,
+}
+
+impl RouteNode {
+    fn insert(&mut self, segments: &[&str], handler: RouteHandlerFn) -> HushResult<()> {
+        let Some((segment, rest)) = segments.split_first() else {
+            if self.handler.is_some() {
+                return Err(HushError::InvalidParameter);
+            }
+            self.handler = Some(handler);
+            return Ok(());
+        };
+
+        match parse_segment(segment) {
+            Segment::Literal(literal) => {
+                self.literal_children
+                    .entry(literal.to_string())
+                    .or_default()
+                    .insert(rest, handler)
+            }
+            Segment::Param(name) => {
+                if let Some((existing_name, _)) = &self.param_child {
+                    if existing_name != name {
+                        // 同一位置已有不同名字的参数，视为冲突 | conflicting param name at this position
+                        return Err(HushError::InvalidParameter);
+                    }
+                }
+                let (_, node) = self
+                    .param_child
+                    .get_or_insert_with(|| (name.to_string(), Box::new(RouteNode::default())));
+                node.insert(rest, handler)
+            }
+            Segment::Wildcard(name) => {
+                // 通配符必须是模式的最后一段 | wildcard must be the last segment in the pattern
+                if !rest.is_empty() {
+                    return Err(HushError::InvalidParameter);
+                }
+                if let Some((existing_name, existing_node)) = &self.wildcard_child {
+                    if existing_name != name || existing_node.handler.is_some() {
+                        return Err(HushError::InvalidParameter);
+                    }
+                }
+                let (_, node) = self
+                    .wildcard_child
+                    .get_or_insert_with(|| (name.to_string(), Box::new(RouteNode::default())));
+                node.handler = Some(handler);
+                Ok(())
+            }
+        }
+    }
+
+    /// 按字面量 > 参数 > 通配符的优先级匹配路径，返回处理函数和绑定的参数
+    /// Match segments preferring literal > param > wildcard, returning the handler and bound params
+    fn find(&self, segments: &[&str], bindings: &mut Vec<(String, String)>) -> Option<RouteHandlerFn> {
+        let Some((segment, rest)) = segments.split_first() else {
+            return self.handler;
+        };
+
+        if let Some(child) = self.literal_children.get(*segment) {
+            let before = bindings.len();
+            if let Some(handler) = child.find(rest, bindings) {
+                return Some(handler);
+            }
+            bindings.truncate(before);
+        }
+
+        if let Some((name, node)) = &self.param_child {
+            let before = bindings.len();
+            bindings.push((name.clone(), (*segment).to_string()));
+            if let Some(handler) = node.find(rest, bindings) {
+                return Some(handler);
+            }
+            bindings.truncate(before);
+        }
+
+        if let Some((name, node)) = &self.wildcard_child {
+            if let Some(handler) = node.handler {
+                bindings.push((name.clone(), segments.join("/")));
+                return Some(handler);
+            }
+        }
+
+        None
+    }
+}
+
+/// 序列化绑定的路径参数为 "name=value&name2=value2" 形式的 C 字符串
+/// Serialize bound path params as a "name=value&name2=value2" C string
+fn serialize_params(bindings: &[(String, String)]) -> CString {
+    let encoded = bindings
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&");
+    CString::new(encoded).unwrap_or_else(|_| CString::new("").unwrap())
+}
+
+/// 解析一个 `application/x-www-form-urlencoded` 风格的查询字符串，按 `key` 查找对应的值
+/// 并完成百分号解码；未找到时返回空指针。调用方负责对返回值调用 `rust_free_string`。
+/// Parse an `application/x-www-form-urlencoded`-style query string, look up `key` and
+/// percent-decode the value; returns a null pointer if not found. The caller owns the
+/// returned string and must release it with `rust_free_string`.
+#[unsafe(no_mangle)]
+pub extern "C" fn hush_query_get(query: *const c_char, key: *const c_char) -> *const c_char {
+    if query.is_null() || key.is_null() {
+        return std::ptr::null();
+    }
+
+    let (query_str, key_str) = unsafe {
+        (
+            CStr::from_ptr(query).to_string_lossy().to_string(),
+            CStr::from_ptr(key).to_string_lossy().to_string(),
+        )
+    };
+
+    for pair in query_str.split('&') {
+        let mut parts = pair.splitn(2, '=');
+        let Some(name) = parts.next() else { continue };
+        if percent_decode(name) != key_str {
+            continue;
+        }
+        let value = parts.next().unwrap_or("");
+        return CString::new(percent_decode(value)).map(CString::into_raw).unwrap_or(std::ptr::null_mut());
+    }
+
+    std::ptr::null()
+}
+
+/// 在一个 "Name: Value\n..." 形式的 header 块中按名称（大小写不敏感）查找值；
+/// 未找到时返回空指针。调用方负责对返回值调用 `rust_free_string`。
+/// Look up a value by name (case-insensitive) in a "Name: Value\n..." header block;
+/// returns a null pointer if not found. The caller owns the returned string and must
+/// release it with `rust_free_string`.
+#[unsafe(no_mangle)]
+pub extern "C" fn hush_header_get(headers: *const c_char, name: *const c_char) -> *const c_char {
+    if headers.is_null() || name.is_null() {
+        return std::ptr::null();
+    }
+
+    let (headers_str, name_str) = unsafe {
+        (
+            CStr::from_ptr(headers).to_string_lossy().to_string(),
+            CStr::from_ptr(name).to_string_lossy().to_string(),
+        )
+    };
+
+    for line in headers_str.lines() {
+        if let Some((header_name, value)) = line.split_once(':') {
+            if header_name.trim().eq_ignore_ascii_case(name_str.trim()) {
+                return CString::new(value.trim()).map(CString::into_raw).unwrap_or(std::ptr::null_mut());
+            }
+        }
+    }
+
+    std::ptr::null()
+}
+
+/// 简单的百分号解码，同时把 `+` 当作空格处理（查询字符串的惯例）
+/// A small percent-decoder that also treats `+` as a space, per query-string convention
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            b'%' if i + 2 < bytes.len() => {
+                if let Ok(value) = u8::from_str_radix(&input[i + 1..i + 3], 16) {
+                    out.push(value);
+                    i += 3;
+                } else {
+                    out.push(bytes[i]);
+                    i += 1;
+                }
+            }
+            byte => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// ============================================================================
+// C 兼容的响应结构 | C-Compatible Response Struct | C互換のレスポンス構造体
+// ============================================================================
+//
+// Zig 处理函数不再只返回正文字符串，而是填充这个结构体，从而可以控制状态码、
+// headers 和 Content-Type，而不是被硬编码为 200 + text/plain。
+// Zig handlers no longer just return a body string — they fill in this struct,
+// giving control over the status code, headers and Content-Type instead of
+// being hard-coded to 200 + text/plain.
+
+/// C 兼容的响应结构：headers 是以换行符分隔的 "Name: Value" 块
+/// C-compatible response struct: headers is a newline-delimited block of "Name: Value" lines
+#[repr(C)]
+pub struct CHushResponse {
+    pub status: u16,
+    pub content_type: *const c_char,
+    pub headers: *const c_char,
+    pub body: *const c_char,
+}
+
+/// 将 `HushError` 映射到它对应的 HTTP 状态码 | Map a `HushError` to its natural HTTP status code
+fn hush_error_status(error: &HushError) -> actix_web::http::StatusCode {
+    use actix_web::http::StatusCode;
+    match error {
+        HushError::RouteNotFound => StatusCode::NOT_FOUND,
+        HushError::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+        HushError::AuthenticationFailed | HushError::InvalidToken | HushError::TokenExpired => {
+            StatusCode::UNAUTHORIZED
+        }
+        HushError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// 在堆上构造一个自持有的 `CHushResponse`，供内部的 404/405/500 等响应
+/// 也能像 Zig 返回的响应一样流经中间件的 after 钩子。
+/// Build a self-owned `CHushResponse` on the heap so internal 404/405/500
+/// responses can flow through middleware after-hooks just like Zig's own responses.
+fn make_c_response(status: u16, body: &str) -> *mut CHushResponse {
+    let body_cstr = CString::new(body).unwrap_or_else(|_| CString::new("").unwrap());
+    Box::into_raw(Box::new(CHushResponse {
+        status,
+        content_type: std::ptr::null(),
+        headers: std::ptr::null(),
+        body: body_cstr.into_raw(),
+    }))
+}
+
+/// 将 Zig 填充的 `CHushResponse` 翻译为 actix-web 的 `HttpResponse`
+/// Translate a `CHushResponse` filled in by Zig into an actix-web `HttpResponse`
+unsafe fn translate_response(response: *mut CHushResponse) -> HttpResponse {
+    unsafe {
+        let c_response = &*response;
+        let status = actix_web::http::StatusCode::from_u16(c_response.status)
+            .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+        let mut builder = HttpResponse::build(status);
+
+        if !c_response.content_type.is_null() {
+            let content_type = CStr::from_ptr(c_response.content_type).to_string_lossy();
+            builder.insert_header(("Content-Type", content_type.as_ref()));
+        }
+
+        if !c_response.headers.is_null() {
+            let headers_block = CStr::from_ptr(c_response.headers).to_string_lossy();
+            for line in headers_block.lines() {
+                if let Some((name, value)) = line.split_once(':') {
+                    builder.insert_header((name.trim(), value.trim()));
+                }
+            }
+        }
+
+        let body = if c_response.body.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(c_response.body).to_string_lossy().to_string()
+        };
+
+        builder.body(body)
+    }
+}
+
+/// 释放 `CHushResponse`：结构体本身按 Rust `Box` 释放，每个非空字符串字段按 `CString` 释放，
+/// 即 `make_c_response`/`hush_string_clone` 产生的分配；`hush_malloc` 分配的内存块不能用这种方式释放
+/// Release a `CHushResponse`: the struct itself is freed as a Rust `Box` and each non-null string
+/// field as a `CString`, i.e. the allocations `make_c_response`/`hush_string_clone` produce; blocks
+/// from `hush_malloc` are not freed correctly this way
+#[unsafe(no_mangle)]
+pub extern "C" fn hush_free_response(response: *mut CHushResponse) {
+    if response.is_null() {
+        return;
+    }
+
+    unsafe {
+        let owned = Box::from_raw(response);
+        if !owned.content_type.is_null() {
+            let _ = CString::from_raw(owned.content_type as *mut c_char);
+        }
+        if !owned.headers.is_null() {
+            let _ = CString::from_raw(owned.headers as *mut c_char);
+        }
+        if !owned.body.is_null() {
+            let _ = CString::from_raw(owned.body as *mut c_char);
+        }
+    }
+}
+
 // ============================================================================
 // Web 框架核心数据结构 | Web Framework Core Data Structures | Webフレームワークのコアデータ構造
 // ============================================================================
 
-// Web 服务器结构体，包含路由映射表 | Web server struct containing route mapping table | ルートマッピングテーブルを含むWebサーバー構造体
+/// 请求前置中间件：收到 method, path, headers（"Name: Value\n..." 块）, body；
+/// 返回空指针表示放行，返回非空指针则短路并直接使用该响应。
+/// Before-request middleware: receives method, path, headers ("Name: Value\n..." block), body;
+/// a null return means "continue", a non-null return short-circuits with that response.
+pub type BeforeMiddlewareFn = extern "C" fn(*const c_char, *const c_char, *const c_char, *const c_char) -> *mut CHushResponse;
+
+/// 响应后置中间件：收到即将发出的响应，返回 null 表示不修改，否则替换为返回值
+/// After-response middleware: receives the outgoing response; a null return leaves it unchanged, otherwise replaces it
+pub type AfterMiddlewareFn = extern "C" fn(*mut CHushResponse) -> *mut CHushResponse;
+
+/// 一个已注册中间件的前置/后置钩子对 | A registered middleware's before/after hook pair
+#[derive(Clone, Copy)]
+struct MiddlewareEntry {
+    before: Option<BeforeMiddlewareFn>,
+    after: Option<AfterMiddlewareFn>,
+}
+
+// Web 服务器结构体，每个 HTTP 方法对应一棵路由 Trie | Web server struct, one route trie per HTTP method | 各HTTPメソッドに対応するルートTrieを持つWebサーバー構造体
 pub struct WebServer {
-    // 路由存储：使用 "METHOD:PATH" 格式作为键 | Route storage: using "METHOD:PATH" format as key | ルート保存："METHOD:PATH"形式をキーとして使用
-    // 例如："GET:/", "POST:/users" | Examples: "GET:/", "POST:/users" | 例："GET:/", "POST:/users"
     // Arc<Mutex<>> 确保多线程安全访问 | Arc<Mutex<>> ensures thread-safe access | Arc<Mutex<>>はマルチスレッドセーフなアクセスを保証
-    // 值是指向 Zig 处理函数的函数指针，现在接收三个参数：method, path, body | Value is function pointer to Zig handler function, now receives three parameters: method, path, body | 値はZigハンドラ関数への関数ポインタ、現在3つのパラメータを受け取る：method, path, body
-    routes: Arc<Mutex<HashMap<String, extern "C" fn(*const c_char, *const c_char, *const c_char) -> *const c_char>>>,
+    routes: Arc<Mutex<HashMap<String, RouteNode>>>,
+    // 中间件链，按注册顺序依次执行前置钩子，再以相反顺序执行后置钩子 | Middleware chain: before-hooks run in registration order, after-hooks run in reverse
+    middlewares: Arc<Mutex<Vec<MiddlewareEntry>>>,
+    // 服务器是否已绑定并正在运行，停止后清除 | Whether the server is bound and running; cleared once it stops
+    active: AtomicBool,
+    // 运行中服务器的句柄，由 `web_server_start` 在绑定成功后填入，供 `web_server_stop` 调用
+    // Handle to the running server, filled in by `web_server_start` once bound, used by `web_server_stop`
+    handle: Mutex<Option<actix_web::dev::ServerHandle>>,
 }
 
-// 全局服务器实例指针，用于在异步处理函数中访问路由 | Global server instance pointer for accessing routes in async handlers | 非同期ハンドラ関数でルートにアクセスするためのグローバルサーバーインスタンスポインタ
-// 注意：使用全局状态不是最佳实践，但简化了 FFI 接口设计 | Note: Using global state is not best practice, but simplifies FFI interface design | 注意：グローバル状態の使用はベストプラクティスではないが、FFIインターフェース設計を簡素化
-static mut GLOBAL_SERVER: Option<*mut WebServer> = None;
+/// 服务器注册表条目的唯一 id，由 `web_server_new` 分配并返回给调用方
+/// Opaque id for a registry entry, allocated by `web_server_new` and handed back to the caller
+pub type ServerId = u64;
+
+/// 服务器注册表：将不透明 id 映射到共享的 `WebServer`，替代此前不安全的
+/// `static mut GLOBAL_SERVER` 单例指针，使一个进程内可以同时运行多个服务器。
+/// Server registry: maps an opaque id to a shared `WebServer`, replacing the
+/// previous unsound `static mut GLOBAL_SERVER` singleton pointer and allowing
+/// several servers to run concurrently in one process.
+static SERVER_REGISTRY: OnceLock<Mutex<HashMap<ServerId, Arc<WebServer>>>> = OnceLock::new();
+static NEXT_SERVER_ID: AtomicU64 = AtomicU64::new(1);
+
+fn server_registry() -> &'static Mutex<HashMap<ServerId, Arc<WebServer>>> {
+    SERVER_REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 按 id 查找已注册的服务器 | Look up a registered server by id
+fn lookup_server(id: ServerId) -> Option<Arc<WebServer>> {
+    server_registry().lock().ok()?.get(&id).cloned()
+}
 
 // ============================================================================
 // Web 框架 FFI 接口函数 | Web Framework FFI Interface Functions | WebフレームワークFFIインターフェース関数
 // ============================================================================
 
 // 创建新的 web 服务器实例 | Create new web server instance | 新しいWebサーバーインスタンスを作成
-// 返回值：指向 WebServer 结构体的原始指针 | Return: Raw pointer to WebServer struct | 戻り値：WebServer構造体への生ポインタ
-// 注意：调用方负责最终调用 web_server_free() 释放内存 | Note: Caller is responsible for calling web_server_free() to release memory | 注意：呼び出し側は最終的にweb_server_free()を呼び出してメモリを解放する責任がある
+// 返回值：该服务器在注册表中的不透明 id，而非原始指针 | Return: The server's opaque id in the registry, not a raw pointer | 戻り値：生ポインタではなく、レジストリ内のこのサーバーの不透明なID
+// 多个服务器可以在同一进程中并存，各自拥有独立的 id | Multiple servers can coexist in the same process, each with its own id | 複数のサーバーが同一プロセス内に共存でき、それぞれ独自のIDを持つ
+// 注意：调用方负责最终调用 web_server_free() 释放注册表条目 | Note: Caller is responsible for calling web_server_free() to release the registry entry | 注意：呼び出し側は最終的にweb_server_free()を呼び出してレジストリエントリを解放する責任がある
 #[unsafe(no_mangle)]
-pub extern "C" fn web_server_new() -> *mut WebServer {
-    // 在堆上创建 WebServer 实例 | Create WebServer instance on heap | ヒープ上にWebServerインスタンスを作成
-    let server = Box::new(WebServer {
+pub extern "C" fn web_server_new() -> ServerId {
+    let server = Arc::new(WebServer {
         routes: Arc::new(Mutex::new(HashMap::new())), // 初始化空的路由表 | Initialize empty route table | 空のルートテーブルを初期化
+        middlewares: Arc::new(Mutex::new(Vec::new())), // 初始化空的中间件链 | Initialize empty middleware chain | 空のミドルウェアチェーンを初期化
+        active: AtomicBool::new(false),
+        handle: Mutex::new(None),
     });
-    
-    // 将 Box 转换为原始指针，转移所有权 | Convert Box to raw pointer, transfer ownership | Boxを生ポインタに変換し、所有権を移転
-    let server_ptr = Box::into_raw(server);
 
-    // 设置全局服务器引用，供异步处理函数使用 | Set global server reference for async handler functions | 非同期ハンドラ関数で使用するためのグローバルサーバー参照を設定
-    unsafe {
-        GLOBAL_SERVER = Some(server_ptr);
+    // 分配一个新的不透明 id 并注册到全局表中 | Allocate a fresh opaque id and register it in the global table | 新しい不透明なIDを割り当て、グローバルテーブルに登録
+    let id = NEXT_SERVER_ID.fetch_add(1, Ordering::Relaxed);
+    if let Ok(mut registry) = server_registry().lock() {
+        registry.insert(id, server);
     }
 
-    server_ptr
+    id
 }
 
 // 添加路由到服务器 | Add route to server | サーバーにルートを追加
 // 参数说明 | Parameters | パラメータ:
 // - server: 服务器实例指针 | server: Server instance pointer | server: サーバーインスタンスポインタ
 // - method: HTTP 方法（如 "GET", "POST"）| method: HTTP method (e.g. "GET", "POST") | method: HTTPメソッド（例："GET", "POST"）
-// - path: 路由路径（如 "/", "/users"）| path: Route path (e.g. "/", "/users") | path: ルートパス（例："/", "/users"）
-// - handler: Zig 处理函数指针，现在接收 method, path, body 三个参数 | handler: Zig handler function pointer, now receives method, path, body parameters | handler: Zigハンドラ関数ポインタ、現在method, path, bodyの3つのパラメータを受け取る
+// - path: 路由模式，支持 "{id}" 参数段和 "{tail:*}" 尾部通配符 | path: Route pattern, supports "{id}" param segments and "{tail:*}" trailing wildcards
+// - handler: Zig 处理函数指针，接收 method, path, query, headers, body, params 六个参数 | handler: Zig handler function pointer, receives method, path, query, headers, body, params
+//
+// 冲突的模式（同一方法、同一位置的不同参数名，或重复注册同一条路由）会被拒绝，
+// 并通过 `hush_get_last_error` 可查询的 `HushError::InvalidParameter` 记录下来。
+// Conflicting patterns (different param names at the same position for the same
+// method, or re-registering the same route) are rejected and recorded as
+// `HushError::InvalidParameter`, retrievable via `hush_get_last_error`.
 #[unsafe(no_mangle)]
 pub extern "C" fn web_server_add_route(
-    server: *mut WebServer,
+    server: ServerId,
     method: *const c_char,
     path: *const c_char,
-    handler: extern "C" fn(*const c_char, *const c_char, *const c_char) -> *const c_char,
+    handler: RouteHandlerFn,
 ) {
     // 参数有效性检查 | Parameter validity check | パラメータの有効性チェック
-    if server.is_null() || method.is_null() || path.is_null() {
+    if method.is_null() || path.is_null() {
+        set_last_error(HushError::NullPointer);
         return;
     }
 
+    let Some(server_ref) = lookup_server(server) else {
+        set_last_error(HushError::InvalidParameter);
+        return;
+    };
+
     unsafe {
         // 将 C 字符串转换为 Rust 字符串 | Convert C strings to Rust strings | C文字列をRust文字列に変換
         let method_str = CStr::from_ptr(method).to_string_lossy().to_string();
         let path_str = CStr::from_ptr(path).to_string_lossy().to_string();
-        
-        // 创建路由键：格式为 "METHOD:PATH" | Create route key: format "METHOD:PATH" | ルートキーを作成：形式は"METHOD:PATH"
-        let route_key = format!("{}:{}", method_str, path_str);
-        let server_ref = &*server;
+
+        let segments: Vec<&str> = path_str.split('/').filter(|s| !s.is_empty()).collect();
 
         // 获取路由表的互斥锁并插入新路由 | Acquire route table mutex lock and insert new route | ルートテーブルのミューテックスロックを取得し、新しいルートを挿入
         if let Ok(mut routes) = server_ref.routes.lock() {
-            routes.insert(route_key, handler);
+            let root = routes.entry(method_str).or_default();
+            if let Err(error) = root.insert(&segments, handler) {
+                set_last_error(error);
+            }
         }
     }
 }
 
+// 添加中间件到服务器 | Add middleware to server | サーバーにミドルウェアを追加
+// `before`/`after` 均可为空指针，但不能两者都为空。中间件按注册顺序组成一条链：
+// 请求先依次流经各中间件的 `before` 钩子再进入路由分发，响应再以相反顺序流经 `after` 钩子。
+// 这使得 Zig 可以实现鉴权（配合 `HushError::AuthenticationFailed`/`InvalidToken`）、
+// 请求日志和响应压缩，而无需改动 Rust 核心。
+// `before`/`after` may each be null, but not both. Middleware forms a chain in
+// registration order: the request flows through each middleware's `before`
+// hook before dispatch, and the response flows back through `after` hooks in
+// reverse order. This lets Zig implement auth (tying into
+// `HushError::AuthenticationFailed`/`InvalidToken`), request logging and
+// response compression without touching the Rust core.
+#[unsafe(no_mangle)]
+pub extern "C" fn web_server_add_middleware(
+    server: ServerId,
+    before: Option<BeforeMiddlewareFn>,
+    after: Option<AfterMiddlewareFn>,
+) {
+    if before.is_none() && after.is_none() {
+        set_last_error(HushError::InvalidParameter);
+        return;
+    }
+
+    let Some(server_ref) = lookup_server(server) else {
+        set_last_error(HushError::InvalidParameter);
+        return;
+    };
+
+    if let Ok(mut middlewares) = server_ref.middlewares.lock() {
+        middlewares.push(MiddlewareEntry { before, after });
+    }
+}
+
 // ============================================================================
 // HTTP 请求处理核心逻辑 | HTTP Request Processing Core Logic | HTTPリクエスト処理のコアロジック
 // ============================================================================
 
 // 通用路由处理函数，处理所有传入的 HTTP 请求 | Generic route handler for all incoming HTTP requests | すべての受信HTTPリクエストを処理する汎用ルートハンドラ
 // 此函数由 actix-web 框架调用，负责路由分发和 Zig 处理函数调用 | Called by actix-web framework, responsible for route dispatching and Zig handler invocation | この関数はactix-webフレームワークによって呼び出され、ルートディスパッチとZigハンドラ関数の呼び出しを担当
-async fn handle_request(req: HttpRequest, body: Bytes) -> HttpResponse {
+// `server` 通过 `app_data` 注入，绑定到这个 actix 应用实例在 `web_server_start` 中捕获的那台 `WebServer`，
+// 取代了此前通过 `GLOBAL_SERVER` 单例解析服务器的做法 | `server` is injected via `app_data`, bound to the
+// `WebServer` captured for this actix app instance in `web_server_start`, replacing the previous
+// `GLOBAL_SERVER` singleton lookup
+async fn handle_request(req: HttpRequest, body: Bytes, server: web::Data<Arc<WebServer>>) -> HttpResponse {
     // 提取 HTTP 方法和路径 | Extract HTTP method and path | HTTPメソッドとパスを抽出
     let method = req.method().as_str();  // 如 "GET", "POST" | e.g. "GET", "POST" | 例："GET", "POST"
     let path = req.path();               // 如 "/", "/users" | e.g. "/", "/users" | 例："/", "/users"
-    
+
     // 将请求体转换为字符串 | Convert request body to string | リクエストボディを文字列に変換
     let body_str = String::from_utf8_lossy(&body);
-    
-    // 构造路由键用于查找处理函数 | Construct route key for handler lookup | ハンドラ検索用のルートキーを構築
-    let route_key = format!("{}:{}", method, path);
+
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    let method_cstr = CString::new(method).unwrap();
+    let path_cstr = CString::new(path).unwrap();
+    let body_cstr = CString::new(body_str.as_ref()).unwrap();
+    let query_cstr = CString::new(req.query_string()).unwrap_or_else(|_| CString::new("").unwrap());
+    let headers_block = req
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| value.to_str().ok().map(|v| format!("{}: {}", name, v)))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let headers_cstr = CString::new(headers_block).unwrap_or_else(|_| CString::new("").unwrap());
+
+    let server_ref: &WebServer = &server;
 
     unsafe {
-        // 检查全局服务器实例是否存在 | Check if global server instance exists | グローバルサーバーインスタンスが存在するかチェック
-        if let Some(server_ptr) = GLOBAL_SERVER {
-            let server_ref = &*server_ptr;
-
-            // 获取路由表的读锁 | Acquire read lock on route table | ルートテーブルの読み取りロックを取得
-            if let Ok(routes) = server_ref.routes.lock() {
-                // 查找匹配的路由处理函数 | Look for matching route handler | 一致するルートハンドラを検索
-                if let Some(handler) = routes.get(&route_key) {
-                    // 创建方法、路径和请求体的 C 字符串，传递给 Zig | Create C strings for method, path and body to pass to Zig | メソッド、パス、リクエストボディのC文字列を作成してZigに渡す
-                    let method_cstr = CString::new(method).unwrap();
-                    let path_cstr = CString::new(path).unwrap();
-                    let body_cstr = CString::new(body_str.as_ref()).unwrap();
-                    
-                    // 调用 Zig 处理函数，传递方法、路径和请求体参数 | Call Zig handler function with method, path and body parameters | Zigハンドラ関数を呼び出し、メソッド、パス、ボディパラメータを渡す
-                    let response_ptr = handler(method_cstr.as_ptr(), path_cstr.as_ptr(), body_cstr.as_ptr());
-
-                    // 检查 Zig 函数是否返回有效响应 | Check if Zig function returned valid response | Zig関数が有効な応答を返したかチェック
-                    if !response_ptr.is_null() {
-                        let response_str = CStr::from_ptr(response_ptr).to_string_lossy();
-                        return HttpResponse::Ok().body(response_str.to_string());
-                    }
+        // 依次执行中间件的 before 钩子，记录已经执行过 before 的前缀，
+        // 以便之后只对这些中间件执行 after 钩子 | Run middleware before-hooks in order,
+        // tracking how many ran so only those get their after-hooks invoked
+        let middlewares = server_ref.middlewares.lock().map(|m| m.clone()).unwrap_or_default();
+        let mut executed = 0usize;
+        let mut response_ptr: Option<*mut CHushResponse> = None;
+
+        for entry in &middlewares {
+            executed += 1;
+            if let Some(before) = entry.before {
+                let short_circuit = before(
+                    method_cstr.as_ptr(),
+                    path_cstr.as_ptr(),
+                    headers_cstr.as_ptr(),
+                    body_cstr.as_ptr(),
+                );
+                if !short_circuit.is_null() {
+                    response_ptr = Some(short_circuit);
+                    break;
+                }
+            }
+        }
+
+        // 没有中间件短路，按 Trie 分发到路由处理函数 | No middleware short-circuited — dispatch via the route trie
+        if response_ptr.is_none() {
+            response_ptr = Some(dispatch_route(
+                server_ref,
+                method,
+                &segments,
+                &method_cstr,
+                &path_cstr,
+                &query_cstr,
+                &headers_cstr,
+                &body_cstr,
+            ));
+        }
+
+        let mut response_ptr = response_ptr.unwrap();
+
+        // 以相反顺序执行已运行过 before 钩子的中间件的 after 钩子
+        // Run after-hooks, in reverse, for the middlewares whose before-hook ran
+        for entry in middlewares[..executed].iter().rev() {
+            if let Some(after) = entry.after {
+                let replaced = after(response_ptr);
+                if !replaced.is_null() && replaced != response_ptr {
+                    hush_free_response(response_ptr);
+                    response_ptr = replaced;
                 }
             }
         }
+
+        let http_response = translate_response(response_ptr);
+        hush_free_response(response_ptr);
+        http_response
+    }
+}
+
+/// 沿 Trie 分发请求到匹配的路由处理函数，未匹配时返回 404/405
+/// Dispatch the request along the trie to a matching route handler, returning 404/405 when unmatched
+unsafe fn dispatch_route(
+    server_ref: &WebServer,
+    method: &str,
+    segments: &[&str],
+    method_cstr: &CString,
+    path_cstr: &CString,
+    query_cstr: &CString,
+    headers_cstr: &CString,
+    body_cstr: &CString,
+) -> *mut CHushResponse {
+    let Ok(routes) = server_ref.routes.lock() else {
+        return make_c_response(500, "Failed to acquire route table lock");
+    };
+
+    if let Some(root) = routes.get(method) {
+        let mut bindings = Vec::new();
+        if let Some(handler) = root.find(segments, &mut bindings) {
+            let params_cstr = serialize_params(&bindings);
+            let response_ptr = handler(
+                method_cstr.as_ptr(),
+                path_cstr.as_ptr(),
+                query_cstr.as_ptr(),
+                headers_cstr.as_ptr(),
+                body_cstr.as_ptr(),
+                params_cstr.as_ptr(),
+            );
+            if !response_ptr.is_null() {
+                return response_ptr;
+            }
+            return make_c_response(500, &HushError::InternalError("Handler returned a null response".to_string()).to_string());
+        }
+    }
+
+    // 该路径没有匹配到当前方法，检查是否有其他方法能匹配该路径
+    // This path matched no route for the current method — check whether another method would
+    for (other_method, other_root) in routes.iter() {
+        if other_method != method {
+            let mut scratch = Vec::new();
+            if other_root.find(segments, &mut scratch).is_some() {
+                return make_c_response(405, &HushError::MethodNotAllowed.to_string());
+            }
+        }
     }
 
-    // 如果没有找到匹配的路由，返回 404 | Return 404 if no matching route found | 一致するルートが見つからない場合、404を返す
-    HttpResponse::NotFound().body("Route not found")
+    make_c_response(404, &HushError::RouteNotFound.to_string())
 }
 
 // 启动 web 服务器 | Start web server | Webサーバーを起動
 // 参数说明 | Parameters | パラメータ:
-// - server: 服务器实例指针 | server: Server instance pointer | server: サーバーインスタンスポインタ
+// - server: 服务器在注册表中的 id | server: The server's id in the registry | server: レジストリ内のサーバーID
 // - port: 监听端口号 | port: Port number to listen on | port: リッスンするポート番号
 // 注意：此函数在新线程中启动服务器，不会阻塞调用方 | Note: This function starts server in new thread, won't block caller | 注意：この関数は新しいスレッドでサーバーを起動し、呼び出し元をブロックしない
+// `ServerHandle` 在绑定成功后被存入注册表条目，供 `web_server_stop` 发起优雅停机
+// The `ServerHandle` is stashed in the registry entry once bound, so `web_server_stop` can request a graceful shutdown
 #[unsafe(no_mangle)]
-pub extern "C" fn web_server_start(server: *mut WebServer, port: u16) {
-    // 参数有效性检查 | Parameter validity check | パラメータの有効性チェック
-    if server.is_null() {
+pub extern "C" fn web_server_start(server: ServerId, port: u16) {
+    let Some(server_ref) = lookup_server(server) else {
+        set_last_error(HushError::InvalidParameter);
         return;
-    }
+    };
 
     // 在新线程中启动服务器，避免阻塞 Zig 主线程 | Start server in new thread to avoid blocking Zig main thread | 新しいスレッドでサーバーを起動し、Zigメインスレッドのブロックを回避
     thread::spawn(move || {
@@ -185,25 +713,253 @@ pub extern "C" fn web_server_start(server: *mut WebServer, port: u16) {
         rt.block_on(async {
             println!("Starting web framework server on port {}", port);
 
-            // 创建 HTTP 服务器实例 | Create HTTP server instance | HTTPサーバーインスタンスを作成
+            // 创建 HTTP 服务器实例，通过 app_data 注入这台服务器的共享状态 | Create HTTP server instance, injecting this server's shared state via app_data | HTTPサーバーインスタンスを作成し、app_dataでこのサーバーの共有状態を注入
             // default_service: 将所有请求路由到 handle_request 函数 | default_service: Route all requests to handle_request function | default_service: すべてのリクエストをhandle_request関数にルーティング
-            HttpServer::new(|| App::new().default_service(web::route().to(handle_request)))
-                .bind(("127.0.0.1", port))           // 绑定到本地地址和指定端口 | Bind to localhost and specified port | ローカルアドレスと指定ポートにバインド
-                .expect("Failed to bind server")     // 绑定失败时 panic | Panic if binding fails | バインドに失敗した場合panic
-                .run()                               // 启动服务器 | Start server | サーバーを起動
-                .await                               // 等待服务器运行 | Wait for server to run | サーバーの実行を待機
-                .expect("Failed to run server");     // 运行失败时 panic | Panic if server fails to run | サーバーの実行に失敗した場合panic
+            let app_state = server_ref.clone();
+            let http_server = HttpServer::new(move || {
+                App::new()
+                    .app_data(web::Data::new(app_state.clone()))
+                    .default_service(web::route().to(handle_request))
+            })
+            .bind(("127.0.0.1", port))           // 绑定到本地地址和指定端口 | Bind to localhost and specified port | ローカルアドレスと指定ポートにバインド
+            .expect("Failed to bind server");    // 绑定失败时 panic | Panic if binding fails | バインドに失敗した場合panic
+
+            let running = http_server.run();
+            // 绑定成功后记录句柄与活跃状态，供 `web_server_stop` 使用 | Record the handle and active flag once bound, for `web_server_stop` to use | バインド成功後、`web_server_stop`が使用できるようハンドルとアクティブ状態を記録
+            if let Ok(mut handle_slot) = server_ref.handle.lock() {
+                *handle_slot = Some(running.handle());
+            }
+            server_ref.active.store(true, Ordering::SeqCst);
+
+            running.await.expect("Failed to run server"); // 等待服务器运行，直至被停止 | Wait for the server to run until stopped | サーバーが停止されるまで実行を待機
+            server_ref.active.store(false, Ordering::SeqCst);
         });
     });
 }
 
+/// 请求服务器优雅停机，并从注册表中移除这一条目
+/// Request a graceful shutdown of the server and remove its registry entry
+///
+/// 对应 `actix_web::dev::ServerHandle::stop(true)`：等待正在处理的请求完成后再关闭。
+/// Maps to `actix_web::dev::ServerHandle::stop(true)`: lets in-flight requests finish before closing.
+#[unsafe(no_mangle)]
+pub extern "C" fn web_server_stop(server: ServerId) {
+    let Some(server_ref) = server_registry().lock().ok().and_then(|mut registry| registry.remove(&server)) else {
+        set_last_error(HushError::InvalidParameter);
+        return;
+    };
+
+    let Some(handle) = server_ref.handle.lock().ok().and_then(|mut slot| slot.take()) else {
+        return;
+    };
+
+    // `ServerHandle::stop` 是异步的，而这是个同步的 FFI 调用，因此在一个临时的
+    // tokio 运行时上阻塞等待它完成 | `ServerHandle::stop` is async, but this is a
+    // synchronous FFI call, so block on it using a throwaway tokio runtime
+    thread::spawn(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(handle.stop(true));
+    });
+}
+
 // 释放服务器资源 | Free server resources
+// 若服务器仍在运行，应先调用 `web_server_stop`；此函数只移除注册表条目，
+// 底层 `Arc<WebServer>` 在所有克隆（包括运行中线程持有的那一份）释放后才真正析构。
+// If the server is still running, call `web_server_stop` first; this only removes
+// the registry entry — the underlying `Arc<WebServer>` is only dropped once every
+// clone (including the one held by a running server thread) is released.
 #[unsafe(no_mangle)]
-pub extern "C" fn web_server_free(server: *mut WebServer) {
-    if !server.is_null() {
-        unsafe {
-            let _ = Box::from_raw(server);
-            GLOBAL_SERVER = None;
+pub extern "C" fn web_server_free(server: ServerId) {
+    if let Ok(mut registry) = server_registry().lock() {
+        registry.remove(&server);
+    }
+}
+
+/// 查询服务器是否仍在运行（已绑定且尚未被 `web_server_stop` 停止）
+/// Query whether the server is still running (bound and not yet stopped via `web_server_stop`)
+#[unsafe(no_mangle)]
+pub extern "C" fn web_server_is_running(server: ServerId) -> bool {
+    lookup_server(server)
+        .map(|server_ref| server_ref.active.load(Ordering::SeqCst))
+        .unwrap_or(false)
+}
+
+// ============================================================================
+// 出站 HTTP 客户端 | Outbound HTTP Client | 送信HTTPクライアント
+// ============================================================================
+//
+// 此前这个 crate 只能处理入站请求，Zig 代码没有办法调用上游服务。
+// 这里提供一个基于 reqwest 的客户端子系统：共享的 `reqwest::Client` 负责连接池，
+// 运行在独立的 tokio 运行时上，与 `web_server_start` 为每个服务器各自起的运行时互不干扰。
+// Previously this crate could only handle inbound requests — Zig code had no way
+// to call upstream services. This provides a reqwest-backed client subsystem: a
+// shared `reqwest::Client` handles connection pooling, running on its own tokio
+// runtime, independent of the per-server runtimes spawned by `web_server_start`.
+
+/// 客户端在注册表中的不透明 id | Opaque id for a client in the registry
+pub type ClientId = u64;
+
+static CLIENT_REGISTRY: OnceLock<Mutex<HashMap<ClientId, Arc<reqwest::Client>>>> = OnceLock::new();
+static NEXT_CLIENT_ID: AtomicU64 = AtomicU64::new(1);
+static CLIENT_RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+
+fn client_registry() -> &'static Mutex<HashMap<ClientId, Arc<reqwest::Client>>> {
+    CLIENT_REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 所有出站请求共享的独立 tokio 运行时，避免每次请求都新建一个
+/// The dedicated tokio runtime shared by all outbound requests, rather than spinning up a fresh one per call
+fn client_runtime() -> &'static tokio::runtime::Runtime {
+    CLIENT_RUNTIME.get_or_init(|| tokio::runtime::Runtime::new().expect("Failed to create HTTP client runtime"))
+}
+
+/// 创建一个新的 HTTP 客户端，返回它在注册表中的 id | Create a new HTTP client, returning its id in the registry
+/// `timeout_secs` 为 0 表示不设置请求超时 | `timeout_secs` of 0 means no request timeout
+/// `max_redirects` 为重定向跳转的上限 | `max_redirects` caps the number of redirect hops followed
+/// 若设置了 `HTTPS_PROXY` 环境变量，会自动从中读取代理地址 | Automatically picks up a proxy from the `HTTPS_PROXY` environment variable, if set
+#[unsafe(no_mangle)]
+pub extern "C" fn hush_http_client_new(timeout_secs: u64, max_redirects: u32) -> ClientId {
+    let mut builder = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::limited(max_redirects as usize));
+
+    if timeout_secs > 0 {
+        builder = builder.timeout(std::time::Duration::from_secs(timeout_secs));
+    }
+
+    if let Ok(proxy_url) = std::env::var("HTTPS_PROXY") {
+        if let Ok(proxy) = reqwest::Proxy::https(&proxy_url) {
+            builder = builder.proxy(proxy);
+        }
+    }
+
+    let client = match builder.build() {
+        Ok(client) => client,
+        Err(error) => {
+            set_last_error(HushError::HttpError(error.to_string()));
+            reqwest::Client::new()
+        }
+    };
+
+    let id = NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed);
+    if let Ok(mut registry) = client_registry().lock() {
+        registry.insert(id, Arc::new(client));
+    }
+
+    id
+}
+
+/// 释放一个 HTTP 客户端 | Release an HTTP client
+#[unsafe(no_mangle)]
+pub extern "C" fn hush_http_client_free(client: ClientId) {
+    if let Ok(mut registry) = client_registry().lock() {
+        registry.remove(&client);
+    }
+}
+
+/// 发起一次出站 HTTP 请求，返回与入站响应相同的 `CHushResponse`
+/// Perform an outbound HTTP request, returning the same `CHushResponse` used for inbound responses
+///
+/// `headers` 是一个以换行符分隔的 "Name: Value" 块，与 `handle_request` 传给处理函数的格式一致；
+/// `body` 为空指针表示没有请求体。正文总是按原始字节发送，JSON 或表单正文的类型只由
+/// headers 块里的 `Content-Type` 标明，不会被重新编码。
+/// `headers` is a newline-delimited "Name: Value" block, matching the format `handle_request`
+/// passes to handlers; a null `body` means no request body. The body is always sent as raw
+/// bytes; a JSON or form body is marked only by the `Content-Type` in the headers block and is
+/// not re-encoded.
+///
+/// 连接失败、超时等传输层错误会通过 `set_last_error` 记录为 `HushError::Timeout`/`HushError::HttpError`，
+/// 并返回空指针，Zig 可以通过 `hush_get_last_error` 查询详情。
+/// Transport-layer failures like connection errors or timeouts are recorded via `set_last_error` as
+/// `HushError::Timeout`/`HushError::HttpError`, and a null pointer is returned; Zig can inspect the
+/// details via `hush_get_last_error`.
+#[unsafe(no_mangle)]
+pub extern "C" fn hush_http_request(
+    client: ClientId,
+    method: *const c_char,
+    url: *const c_char,
+    headers: *const c_char,
+    body: *const c_char,
+) -> *mut CHushResponse {
+    if method.is_null() || url.is_null() {
+        set_last_error(HushError::NullPointer);
+        return std::ptr::null_mut();
+    }
+
+    let Some(client_ref) = client_registry().lock().ok().and_then(|registry| registry.get(&client).cloned()) else {
+        set_last_error(HushError::InvalidParameter);
+        return std::ptr::null_mut();
+    };
+
+    let (method_str, url_str, headers_str, body_bytes) = unsafe {
+        let method_str = CStr::from_ptr(method).to_string_lossy().to_string();
+        let url_str = CStr::from_ptr(url).to_string_lossy().to_string();
+        let headers_str = if headers.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(headers).to_string_lossy().to_string()
+        };
+        let body_bytes = if body.is_null() {
+            Vec::new()
+        } else {
+            CStr::from_ptr(body).to_bytes().to_vec()
+        };
+        (method_str, url_str, headers_str, body_bytes)
+    };
+
+    let Ok(method) = reqwest::Method::from_bytes(method_str.as_bytes()) else {
+        set_last_error(HushError::InvalidParameter);
+        return std::ptr::null_mut();
+    };
+
+    let result = client_runtime().block_on(async {
+        let mut request = client_ref.request(method, &url_str);
+
+        // Headers 块里已经带上了调用方设置的 Content-Type（application/json、
+        // application/x-www-form-urlencoded 或其他），这里按原始字节发送正文，
+        // 不需要 reqwest 的 .json()/.form() 辅助方法来重新推断它
+        // The headers block already carries whatever Content-Type the caller set
+        // (application/json, application/x-www-form-urlencoded, or otherwise), so
+        // the body is sent as raw bytes without reqwest's .json()/.form() helpers
+        // re-inferring it
+        for line in headers_str.lines() {
+            if let Some((name, value)) = line.split_once(':') {
+                request = request.header(name.trim(), value.trim());
+            }
+        }
+
+        if !body_bytes.is_empty() {
+            request = request.body(body_bytes);
+        }
+
+        let response = request.send().await?;
+        let status = response.status().as_u16();
+        let headers_block = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| value.to_str().ok().map(|v| format!("{}: {}", name, v)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let body_text = response.text().await?;
+        Ok::<(u16, String, String), reqwest::Error>((status, headers_block, body_text))
+    });
+
+    match result {
+        Ok((status, headers_block, body_text)) => {
+            let response = make_c_response(status, &body_text);
+            // `make_c_response` 把 headers 初始化为空指针，这里直接填入，无需先释放
+            // `make_c_response` initializes headers as null, so we fill it in directly with no prior free needed
+            unsafe {
+                (*response).headers = CString::new(headers_block).unwrap_or_else(|_| CString::new("").unwrap()).into_raw();
+            }
+            response
+        }
+        Err(error) => {
+            if error.is_timeout() {
+                set_last_error(HushError::Timeout);
+            } else {
+                set_last_error(HushError::HttpError(error.to_string()));
+            }
+            std::ptr::null_mut()
         }
     }
 }
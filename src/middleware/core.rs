@@ -6,6 +6,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use crate::core::error::{HushError, HushResult};
 use crate::core::types::{RequestContext, ResponseContext, HttpStatus};
+use super::registry::{MiddlewareRegistration, ParamSpec};
 
 /// 中间件执行结果
 #[derive(Debug)]
@@ -64,6 +65,12 @@ pub trait Middleware: Send + Sync {
     fn priority(&self) -> i32 {
         100
     }
+
+    /// 是否为路径作用域中间件（仅在请求路径匹配某个前缀时才会执行）。
+    /// 供 [`MiddlewareChain::scoped_count`]/[`MiddlewareChain::global_count`] 统计使用
+    fn is_scoped(&self) -> bool {
+        false
+    }
 }
 
 /// 函数式中间件包装器
@@ -111,9 +118,73 @@ impl Middleware for FunctionMiddleware {
     }
 }
 
+/// 路径作用域中间件包装器：仅当请求路径以配置的前缀开头时才执行内部中间件，
+/// 否则直接放行给链上的下一个中间件。名称和优先级透传自内部中间件，这样
+/// `middleware_names()`/排序行为和未作用域的中间件保持一致。
+/// Path-scoped middleware wrapper: the inner middleware only runs when the
+/// request path starts with the configured prefix; otherwise it passes
+/// straight through to the next middleware in the chain. Name and priority
+/// are forwarded from the inner middleware so `middleware_names()`/ordering
+/// behave the same as for an unscoped middleware.
+pub struct ScopedMiddleware {
+    prefix: String,
+    inner: Arc<dyn Middleware>,
+}
+
+impl ScopedMiddleware {
+    pub fn new(prefix: String, inner: Arc<dyn Middleware>) -> Self {
+        Self { prefix, inner }
+    }
+}
+
+impl Middleware for ScopedMiddleware {
+    fn process(&self, context: &mut MiddlewareContext, next: NextFunction) -> HushResult<MiddlewareResult> {
+        if context.request.path.starts_with(&self.prefix) {
+            self.inner.process(context, next)
+        } else {
+            next(context)
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.inner.priority()
+    }
+
+    fn is_scoped(&self) -> bool {
+        true
+    }
+}
+
 /// 中间件链管理器
+///
+/// 可以廉价地 `Clone`（中间件实例通过 `Arc` 共享，只复制每个槽位的注册元数据），
+/// 这使得 `Scope` 能够把作用域
+/// 自己的中间件链复制一份、接上作用域的路由处理器，再逐请求执行，而不影响
+/// 原始链上后续请求的状态。
+/// Cheaply `Clone`-able (middleware instances are shared through their `Arc`s;
+/// only each slot's registry metadata is copied), which lets a
+/// `Scope` copy its own middleware chain, append the scope's router handler, and
+/// execute it per request without affecting the original chain's state for later requests.
+#[derive(Clone)]
 pub struct MiddlewareChain {
-    middlewares: Vec<Arc<dyn Middleware>>,
+    middlewares: Vec<MiddlewareSlot>,
+}
+
+/// 链上的一个槽位：实际的中间件实例，加上它在注册表里的元数据（当前配置、
+/// 声明的参数 schema，以及是否支持热重载）。名字对齐由 `name()` 保证——配置
+/// 元数据跟着中间件实例走，不需要单独维护一份按下标对齐的数组。
+/// One slot in the chain: the actual middleware instance plus its registry
+/// metadata (active config, declared param schema, and whether it supports
+/// hot reload). Keeping them in the same struct avoids having to keep a
+/// separately-indexed array in sync.
+#[derive(Clone)]
+struct MiddlewareSlot {
+    middleware: Arc<dyn Middleware>,
+    registration: MiddlewareRegistration,
 }
 
 impl MiddlewareChain {
@@ -122,14 +193,63 @@ impl MiddlewareChain {
             middlewares: Vec::new(),
         }
     }
-    
-    /// 添加中间件
+
+    /// 添加中间件（不支持通过 [`MiddlewareChain::configure`] 热重载）
     pub fn add<M: Middleware + 'static>(&mut self, middleware: M) {
-        self.middlewares.push(Arc::new(middleware));
+        self.add_slot(Arc::new(middleware), MiddlewareRegistration::unconfigured());
+    }
+
+    /// 添加一个可以通过 [`MiddlewareChain::configure`] 用新 JSON 热重载的中间件。
+    /// `config_json` 是它当前生效的配置，`params` 是它声明的参数 schema，
+    /// `factory` 接收新的 JSON 并返回一个全新构造的实例。
+    /// Add a middleware that can be hot-reloaded with new JSON via
+    /// [`MiddlewareChain::configure`]. `config_json` is its currently active
+    /// config, `params` is its declared param schema, and `factory` takes the
+    /// new JSON and returns a freshly constructed instance.
+    pub fn add_configurable<M, F>(&mut self, middleware: M, config_json: String, params: Vec<ParamSpec>, factory: F)
+    where
+        M: Middleware + 'static,
+        F: Fn(&str) -> HushResult<Arc<dyn Middleware>> + Send + Sync + 'static,
+    {
+        let registration = MiddlewareRegistration::new(config_json, params, Arc::new(factory));
+        self.add_slot(Arc::new(middleware), registration);
+    }
+
+    fn add_slot(&mut self, middleware: Arc<dyn Middleware>, registration: MiddlewareRegistration) {
+        self.middlewares.push(MiddlewareSlot { middleware, registration });
         // 按优先级排序
-        self.middlewares.sort_by_key(|m| m.priority());
+        self.middlewares.sort_by_key(|slot| slot.middleware.priority());
     }
-    
+
+    /// 用新的 JSON 配置重新初始化名字匹配的中间件，而不重建整条链。
+    /// 目标中间件必须是通过 [`MiddlewareChain::add_configurable`] 添加的。
+    /// Re-initializes the name-matching middleware from new JSON config,
+    /// without rebuilding the whole chain. The target middleware must have
+    /// been added via [`MiddlewareChain::add_configurable`].
+    pub fn configure(&mut self, name: &str, config_json: &str) -> HushResult<()> {
+        let slot = self.middlewares.iter_mut()
+            .find(|slot| slot.middleware.name() == name)
+            .ok_or_else(|| HushError::InvalidInput(format!("No middleware named '{}'", name)))?;
+
+        let factory = slot.registration.factory.clone().ok_or_else(|| {
+            HushError::InvalidInput(format!("Middleware '{}' does not support runtime configuration", name))
+        })?;
+
+        let new_middleware = factory(config_json)?;
+        slot.middleware = new_middleware;
+        slot.registration.config_json = config_json.to_string();
+        Ok(())
+    }
+
+    /// 以 JSON 数组的形式列出链上每个中间件的名字、当前配置和参数 schema
+    /// Lists every middleware's name, active config, and param schema as a JSON array
+    pub fn registry_json(&self) -> String {
+        let entries: Vec<String> = self.middlewares.iter()
+            .map(|slot| slot.registration.to_json(slot.middleware.name()))
+            .collect();
+        format!("[{}]", entries.join(","))
+    }
+
     /// 添加函数式中间件
     pub fn add_function<F>(&mut self, name: String, handler: F)
     where
@@ -153,60 +273,41 @@ impl MiddlewareChain {
         if self.middlewares.is_empty() {
             return Err(HushError::InternalError("No middlewares or handler defined".to_string()));
         }
-        
-        self.execute_middleware_at_index(0, &mut context)
+
+        let middlewares: Vec<Arc<dyn Middleware>> = self.middlewares.iter()
+            .map(|slot| slot.middleware.clone())
+            .collect();
+
+        match Self::run_from(&middlewares, 0, &mut context)? {
+            MiddlewareResult::Continue => Ok(ResponseContext::with_text(HttpStatus::Ok, "OK")),
+            MiddlewareResult::Response(response) => Ok(response),
+            MiddlewareResult::Error(error) => Err(error),
+        }
     }
-    
-    /// 递归执行指定索引的中间件
-    fn execute_middleware_at_index(&self, index: usize, context: &mut MiddlewareContext) -> HushResult<ResponseContext> {
-        if index >= self.middlewares.len() {
-            // 所有中间件都执行完毕，返回默认响应
-            return Ok(ResponseContext::with_text(HttpStatus::Ok, "OK"));
+
+    /// 从指定索引开始依次执行中间件。每个中间件收到的 `next` 闭包都会真正
+    /// 串联到链上剩余的全部中间件，而不仅仅是再往下一两层——这样一个中间件
+    /// 调用 `next(ctx)` 拿到的永远是后续链条真正产生的结果，使得在 `next`
+    /// 返回之后检查/改写响应（响应阶段回调）对链上任意位置的中间件都成立。
+    /// Runs the chain starting at `index`. Every middleware's `next` closure
+    /// is wired to the *entire* remaining chain rather than only one or two
+    /// levels deep, so a middleware calling `next(ctx)` always observes the
+    /// genuine downstream result — which is what lets a middleware inspect or
+    /// rewrite the response after `next` returns, no matter where it sits in
+    /// the chain.
+    fn run_from(middlewares: &[Arc<dyn Middleware>], index: usize, context: &mut MiddlewareContext) -> HushResult<MiddlewareResult> {
+        if index >= middlewares.len() {
+            return Ok(MiddlewareResult::Continue);
         }
-        
-        let middleware = self.middlewares[index].clone();
-        let next_index = index + 1;
-        let middlewares = self.middlewares.clone();
-        
-        // 创建 next 函数
+
+        let middleware = middlewares[index].clone();
+        let remaining: Vec<Arc<dyn Middleware>> = middlewares[index + 1..].to_vec();
+
         let next: NextFunction = Box::new(move |ctx: &mut MiddlewareContext| -> HushResult<MiddlewareResult> {
-            if next_index >= middlewares.len() {
-                // 没有更多中间件，返回继续
-                return Ok(MiddlewareResult::Continue);
-            }
-            
-            let next_middleware = middlewares[next_index].clone();
-            let next_next_index = next_index + 1;
-            let middlewares_clone = middlewares.clone();
-            
-            // 递归创建下一个 next 函数
-            let recursive_next: NextFunction = Box::new(move |ctx: &mut MiddlewareContext| -> HushResult<MiddlewareResult> {
-                if next_next_index >= middlewares_clone.len() {
-                    return Ok(MiddlewareResult::Continue);
-                }
-                
-                // 简化处理，直接返回 Continue
-                Ok(MiddlewareResult::Continue)
-            });
-            
-            next_middleware.process(ctx, recursive_next)
+            Self::run_from(&remaining, 0, ctx)
         });
-        
-        // 执行当前中间件
-        match middleware.process(context, next)? {
-            MiddlewareResult::Continue => {
-                // 继续执行下一个中间件
-                self.execute_middleware_at_index(next_index, context)
-            }
-            MiddlewareResult::Response(response) => {
-                // 中间件返回了响应，直接返回
-                Ok(response)
-            }
-            MiddlewareResult::Error(error) => {
-                // 中间件执行出错
-                Err(error)
-            }
-        }
+
+        middleware.process(context, next)
     }
     
     /// 获取中间件数量
@@ -221,7 +322,17 @@ impl MiddlewareChain {
     
     /// 获取中间件名称列表
     pub fn middleware_names(&self) -> Vec<String> {
-        self.middlewares.iter().map(|m| m.name().to_string()).collect()
+        self.middlewares.iter().map(|slot| slot.middleware.name().to_string()).collect()
+    }
+
+    /// 全局中间件数量（不限路径前缀，对所有请求生效）
+    pub fn global_count(&self) -> usize {
+        self.middlewares.iter().filter(|slot| !slot.middleware.is_scoped()).count()
+    }
+
+    /// 路径作用域中间件数量（仅在请求路径匹配某个前缀时才执行）
+    pub fn scoped_count(&self) -> usize {
+        self.middlewares.iter().filter(|slot| slot.middleware.is_scoped()).count()
     }
 }
 
@@ -234,6 +345,7 @@ impl Default for MiddlewareChain {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::super::registry::ParamType;
     use crate::core::types::{HttpMethod, HttpStatus};
     
     #[test]
@@ -304,4 +416,68 @@ mod tests {
         assert_eq!(names[0], "high_priority");
         assert_eq!(names[1], "low_priority");
     }
+
+    #[test]
+    fn test_scoped_middleware_only_runs_for_matching_prefix() {
+        let inner = Arc::new(FunctionMiddleware::new("admin_only".to_string(), |ctx, next| {
+            ctx.set_data("admin_only_ran".to_string(), "true".to_string());
+            next(ctx)
+        }));
+        let scoped = ScopedMiddleware::new("/api/admin".to_string(), inner);
+        assert_eq!(scoped.name(), "admin_only");
+        assert!(scoped.is_scoped());
+
+        let next: NextFunction = Box::new(|_ctx| Ok(MiddlewareResult::Continue));
+
+        let mut matching = MiddlewareContext::new(RequestContext::new(HttpMethod::GET, "/api/admin/users".to_string()));
+        scoped.process(&mut matching, next).unwrap();
+        assert_eq!(matching.get_data("admin_only_ran"), Some(&"true".to_string()));
+
+        let next: NextFunction = Box::new(|_ctx| Ok(MiddlewareResult::Continue));
+        let mut non_matching = MiddlewareContext::new(RequestContext::new(HttpMethod::GET, "/public".to_string()));
+        scoped.process(&mut non_matching, next).unwrap();
+        assert_eq!(non_matching.get_data("admin_only_ran"), None);
+    }
+
+    #[test]
+    fn test_chain_global_and_scoped_counts() {
+        let mut chain = MiddlewareChain::new();
+        chain.add_function("global".to_string(), |ctx, next| next(ctx));
+
+        let inner = Arc::new(FunctionMiddleware::new("scoped".to_string(), |ctx, next| next(ctx)));
+        chain.add(ScopedMiddleware::new("/api/admin".to_string(), inner));
+
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain.global_count(), 1);
+        assert_eq!(chain.scoped_count(), 1);
+    }
+
+    #[test]
+    fn test_configure_reinitializes_named_middleware_without_rebuilding_chain() {
+        let mut chain = MiddlewareChain::new();
+        chain.add_function("plain".to_string(), |ctx, next| next(ctx));
+        chain.add_configurable(
+            FunctionMiddleware::new("configurable".to_string(), |ctx, next| next(ctx)),
+            r#"{"threshold":1}"#.to_string(),
+            vec![ParamSpec::new("threshold", ParamType::Number, true)],
+            |config_json| {
+                if config_json.contains("threshold") {
+                    Ok(Arc::new(FunctionMiddleware::new("configurable".to_string(), |ctx, next| next(ctx))) as Arc<dyn Middleware>)
+                } else {
+                    Err(HushError::InvalidInput("missing threshold".to_string()))
+                }
+            },
+        );
+
+        assert_eq!(chain.len(), 2);
+
+        chain.configure("configurable", r#"{"threshold":5}"#).unwrap();
+        assert!(chain.registry_json().contains(r#""threshold":5"#));
+
+        let err = chain.configure("plain", r#"{"threshold":5}"#).unwrap_err();
+        assert!(matches!(err, HushError::InvalidInput(_)));
+
+        let err = chain.configure("missing", r#"{"threshold":5}"#).unwrap_err();
+        assert!(matches!(err, HushError::InvalidInput(_)));
+    }
 }
\ No newline at end of file
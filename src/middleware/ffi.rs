@@ -3,17 +3,39 @@
 // ============================================================================
 
 
-use std::ffi::{c_char, c_int, CString};
+use std::ffi::{c_char, c_int, c_void, CString};
 use std::sync::{Arc, Mutex};
-use crate::core::error::{HushError, set_last_error};
+use crate::core::error::{HushError, HushResult, set_last_error};
 use crate::core::ffi::{from_c_string, to_c_string};
 use crate::core::types::{RequestContext, ResponseContext, HttpMethod, HttpStatus};
-use super::core::{MiddlewareChain, MiddlewareContext, MiddlewareResult};
-use super::builtin::{CorsMiddleware, LoggerMiddleware, AuthMiddleware, RateLimitMiddleware};
+use super::core::{Middleware, MiddlewareChain, MiddlewareContext, MiddlewareResult, NextFunction};
+use super::builtin::{CorsMiddleware, LoggerMiddleware, AuthMiddleware, RateLimitMiddleware, CsrfMiddleware, TimeoutMiddleware};
+use super::session::{SessionMiddleware, InMemorySessionStore, SESSION_DATA_PREFIX};
 
 /// C 兼容的中间件处理函数类型
 pub type HushMiddlewareHandler = extern "C" fn(*mut HushRequestContext, *mut c_char) -> c_int;
 
+/// "预请求"阶段回调：在路由处理器执行之前观察（必要时可后续扩展为修改）请求。
+/// 返回非 0 表示该阶段出错，将中止整条链并返回错误。
+/// Started-phase callback: observes the request before the handler runs.
+/// A non-zero return aborts the chain with an error.
+pub type HushMiddlewareStartedFn = extern "C" fn(*mut HushRequestContext, *mut c_char) -> c_int;
+
+/// "响应改写"阶段回调：在链上更靠内层的中间件/处理器产生响应之后、
+/// 返回给调用方之前，拿到可变的 `HushResponseContext` 以改写状态码/正文。
+/// 返回非 0 表示该阶段出错，将中止整条链并返回错误。
+/// Response-phase callback: receives a mutable `HushResponseContext` after the
+/// inner chain/handler has produced a response, before it propagates back out,
+/// so headers/bodies/status can be rewritten. A non-zero return is an error.
+pub type HushMiddlewareResponseFn = extern "C" fn(*mut HushResponseContext, *mut c_char) -> c_int;
+
+/// "完成"阶段回调：无论链是正常返回响应还是出错，都会在该中间件的处理收尾时
+/// 被调用一次，用于日志记录、指标上报等不影响响应内容的收尾工作。
+/// Finished-phase callback: always invoked once this middleware's processing
+/// winds down (whether the chain produced a response or errored), for
+/// logging/metrics work that doesn't affect the response itself.
+pub type HushMiddlewareFinishedFn = extern "C" fn(*mut c_char);
+
 /// C 兼容的请求上下文结构
 #[repr(C)]
 pub struct HushRequestContext {
@@ -27,6 +49,90 @@ pub struct HushRequestContext {
     pub user_data_count: usize,
     pub user_data_keys: *const *const c_char,
     pub user_data_values: *const *const c_char,
+    /// 内部使用：指向本次调用存活的 Rust `RequestContext`，供
+    /// `hush_request_get_header`/`hush_request_set_user_data` 直接读写，
+    /// 不受限于调用开始时拍下的固定大小 headers/user_data 快照数组。
+    /// C 代码不应直接解引用这个字段，只应原样把整个结构体指针转发给那两个辅助函数。
+    /// Internal use: points at the Rust `RequestContext` alive for this call, so
+    /// `hush_request_get_header`/`hush_request_set_user_data` can read/write it
+    /// directly instead of being limited to the fixed-size headers/user_data
+    /// snapshot arrays captured when the call started. C code should not
+    /// dereference this field itself — only pass the struct pointer through to
+    /// those two helpers.
+    request_ptr: *mut c_void,
+}
+
+/// C 兼容的响应上下文结构，供响应阶段回调观察/改写状态码和正文。
+/// headers 采用和 [`crate::CHushResponse`] 一致的换行分隔 "Name: Value" 文本块，
+/// 而不是另起一套 keys/values 数组——这是本仓库目前唯一真正落地（而非
+/// 简化为空指针）的 C 兼容 headers 表示方式。
+/// C-compatible response context for the response-phase callback to observe
+/// and rewrite the status code and body. Headers use the same newline-delimited
+/// "Name: Value" block as [`crate::CHushResponse`] rather than a separate
+/// keys/values array — the one C-compatible headers representation this repo
+/// actually implements rather than leaving as null placeholders.
+#[repr(C)]
+pub struct HushResponseContext {
+    pub status_code: u16,
+    pub body: *mut c_char,
+    pub body_length: usize,
+    pub headers: *const c_char,
+}
+
+/// 将 Rust ResponseContext 转换为 C 兼容的结构
+fn response_context_to_c(response: &ResponseContext) -> Result<(HushResponseContext, Vec<CString>), HushError> {
+    let mut c_strings = Vec::new();
+
+    let body_str = response.body_as_string().unwrap_or_default();
+    let body_cstr = to_c_string(&body_str)?;
+    c_strings.push(body_cstr);
+    let body_ptr = c_strings.last().unwrap().as_ptr() as *mut c_char;
+
+    let headers_block = response.headers.iter()
+        .map(|(name, value)| format!("{}: {}", name, value))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let headers_cstr = to_c_string(&headers_block)?;
+    c_strings.push(headers_cstr);
+    let headers_ptr = c_strings.last().unwrap().as_ptr();
+
+    let c_response = HushResponseContext {
+        status_code: response.status.as_u16(),
+        body: body_ptr,
+        body_length: response.body.len(),
+        headers: headers_ptr,
+    };
+
+    Ok((c_response, c_strings))
+}
+
+/// 从 C 兼容的结构更新 Rust ResponseContext：状态码更新为回调写回的值
+/// （若不是已知状态码则保持原值），正文替换为回调写回的字节。headers 块
+/// 按 "Name: Value" 逐行解析后合并进响应的 headers。
+/// Updates a Rust `ResponseContext` from the C struct: the status code is
+/// updated to whatever the callback wrote back (left unchanged if it isn't a
+/// recognized status code), the body is replaced with the callback's bytes,
+/// and the headers block is parsed line-by-line as "Name: Value" and merged in.
+fn update_response_context_from_c(response: &mut ResponseContext, c_response: &HushResponseContext) -> Result<(), HushError> {
+    if let Ok(status) = HttpStatus::from_u16(c_response.status_code) {
+        response.status = status;
+    }
+
+    if !c_response.body.is_null() {
+        let body = from_c_string(c_response.body)?;
+        response.set_body(body.into_bytes());
+    }
+
+    if !c_response.headers.is_null() {
+        let headers_block = from_c_string(c_response.headers)?;
+        for line in headers_block.lines() {
+            if let Some((name, value)) = line.split_once(':') {
+                response.add_header(name.trim().to_string(), value.trim().to_string());
+            }
+        }
+    }
+
+    Ok(())
 }
 
 /// C 兼容的中间件链结构
@@ -42,59 +148,427 @@ impl HushMiddleware {
     }
 }
 
-/// 将 Rust RequestContext 转换为 C 兼容的结构
-fn request_context_to_c(context: &RequestContext) -> Result<(HushRequestContext, Vec<CString>), HushError> {
+/// [`request_context_to_c`] 转换结果的配套存储：按字段分配的 `CString` 缓冲区
+/// 以及 headers/user_data 的键值指针数组，都需要和返回的 `HushRequestContext`
+/// 活得一样久，因此打包在一起由调用方持有。
+/// Backing storage for a [`request_context_to_c`] conversion: the per-field
+/// `CString` buffers plus the headers/user_data key/value pointer arrays, all
+/// of which must outlive the returned `HushRequestContext` — bundled together
+/// so the caller can hold them for exactly as long as needed.
+struct RequestContextBacking {
+    _strings: Vec<CString>,
+    _header_keys: Vec<*const c_char>,
+    _header_values: Vec<*const c_char>,
+    _user_data_keys: Vec<*const c_char>,
+    _user_data_values: Vec<*const c_char>,
+}
+
+/// 将 Rust RequestContext 转换为 C 兼容的结构，包括分配并填充 headers 和
+/// user_data 的并行键/值指针数组（此前这两者始终是空指针，仅报告数量）。
+/// Converts a Rust `RequestContext` to its C-compatible struct, including
+/// allocating and populating parallel key/value pointer arrays for headers and
+/// user_data (previously these were always null pointers reporting only a count).
+fn request_context_to_c(context: &mut RequestContext) -> Result<(HushRequestContext, RequestContextBacking), HushError> {
+    let request_ptr = context as *mut RequestContext as *mut c_void;
     let mut c_strings = Vec::new();
-    
+
     // 转换方法
     let method_cstr = to_c_string(context.method.as_str())?;
     c_strings.push(method_cstr);
     let method_ptr = c_strings.last().unwrap().as_ptr();
-    
+
     // 转换路径
     let path_cstr = to_c_string(&context.path)?;
     c_strings.push(path_cstr);
     let path_ptr = c_strings.last().unwrap().as_ptr();
-    
+
     // 转换请求体
     let body_str = context.body_as_string().unwrap_or_default();
     let body_cstr = to_c_string(&body_str)?;
     c_strings.push(body_cstr);
     let body_ptr = c_strings.last().unwrap().as_ptr();
-    
-    // 转换 headers（简化处理，实际应该分配数组）
-    let headers_count = context.headers.len();
-    
-    // 转换 user_data（简化处理）
-    let user_data_count = context.user_data.len();
-    
+
+    // 转换 headers：分配并行的键/值 CString，记录指针供 C 侧读取/改写
+    let mut header_keys = Vec::new();
+    let mut header_values = Vec::new();
+    for (key, value) in &context.headers {
+        let key_cstr = to_c_string(key)?;
+        let value_cstr = to_c_string(value)?;
+        header_keys.push(key_cstr.as_ptr());
+        header_values.push(value_cstr.as_ptr());
+        c_strings.push(key_cstr);
+        c_strings.push(value_cstr);
+    }
+    let headers_count = header_keys.len();
+    let headers_keys_ptr = if header_keys.is_empty() { std::ptr::null() } else { header_keys.as_ptr() };
+    let headers_values_ptr = if header_values.is_empty() { std::ptr::null() } else { header_values.as_ptr() };
+
+    // 转换 user_data：同样分配并行的键/值 CString
+    let mut user_data_keys = Vec::new();
+    let mut user_data_values = Vec::new();
+    for (key, value) in &context.user_data {
+        let key_cstr = to_c_string(key)?;
+        let value_cstr = to_c_string(value)?;
+        user_data_keys.push(key_cstr.as_ptr());
+        user_data_values.push(value_cstr.as_ptr());
+        c_strings.push(key_cstr);
+        c_strings.push(value_cstr);
+    }
+    let user_data_count = user_data_keys.len();
+    let user_data_keys_ptr = if user_data_keys.is_empty() { std::ptr::null() } else { user_data_keys.as_ptr() };
+    let user_data_values_ptr = if user_data_values.is_empty() { std::ptr::null() } else { user_data_values.as_ptr() };
+
     let c_context = HushRequestContext {
         method: method_ptr,
         path: path_ptr,
         body: body_ptr,
         body_length: context.body.len(),
         headers_count,
-        headers_keys: std::ptr::null(),
-        headers_values: std::ptr::null(),
+        headers_keys: headers_keys_ptr,
+        headers_values: headers_values_ptr,
         user_data_count,
-        user_data_keys: std::ptr::null(),
-        user_data_values: std::ptr::null(),
+        user_data_keys: user_data_keys_ptr,
+        user_data_values: user_data_values_ptr,
+        request_ptr,
     };
-    
-    Ok((c_context, c_strings))
+
+    let backing = RequestContextBacking {
+        _strings: c_strings,
+        _header_keys: header_keys,
+        _header_values: header_values,
+        _user_data_keys: user_data_keys,
+        _user_data_values: user_data_values,
+    };
+
+    Ok((c_context, backing))
 }
 
-/// 从 C 兼容的结构更新 Rust RequestContext
-fn update_request_context_from_c(_context: &mut RequestContext, _c_context: &HushRequestContext) -> Result<(), HushError> {
-    // 这里可以从 C 结构中读取修改后的数据并更新 Rust 结构
-    // 为了简化，我们暂时跳过这个实现
+/// 从 C 兼容的结构把 headers、user_data 和请求体读回 Rust RequestContext
+/// （method/path 这里不回写，C 中间件不应该改变路由匹配用到的请求身份）。
+/// Reads headers, user_data, and the body back from the C struct into the
+/// Rust `RequestContext` (method/path are intentionally left alone — a C
+/// middleware shouldn't be able to change the request identity routing matched on).
+fn update_request_context_from_c(context: &mut RequestContext, c_context: &HushRequestContext) -> Result<(), HushError> {
+    if c_context.headers_count > 0 && !c_context.headers_keys.is_null() && !c_context.headers_values.is_null() {
+        context.headers.clear();
+        unsafe {
+            let keys = std::slice::from_raw_parts(c_context.headers_keys, c_context.headers_count);
+            let values = std::slice::from_raw_parts(c_context.headers_values, c_context.headers_count);
+            for i in 0..c_context.headers_count {
+                if keys[i].is_null() || values[i].is_null() {
+                    continue;
+                }
+                context.headers.insert(from_c_string(keys[i])?, from_c_string(values[i])?);
+            }
+        }
+    }
+
+    if c_context.user_data_count > 0 && !c_context.user_data_keys.is_null() && !c_context.user_data_values.is_null() {
+        context.user_data.clear();
+        unsafe {
+            let keys = std::slice::from_raw_parts(c_context.user_data_keys, c_context.user_data_count);
+            let values = std::slice::from_raw_parts(c_context.user_data_values, c_context.user_data_count);
+            for i in 0..c_context.user_data_count {
+                if keys[i].is_null() || values[i].is_null() {
+                    continue;
+                }
+                context.user_data.insert(from_c_string(keys[i])?, from_c_string(values[i])?);
+            }
+        }
+    }
+
+    if !c_context.body.is_null() {
+        let body = from_c_string(c_context.body)?;
+        context.set_body(body.into_bytes());
+    }
+
     Ok(())
 }
 
+/// 把最多三个 C 回调（Started/Response/Finished）包装成一个标准的 [`Middleware`]，
+/// 使 FFI 侧的调用方获得和原生 Rust 中间件一样的"前置观察 -> 调用 next ->
+/// 响应改写 -> 收尾"生命周期。三个阶段都是可选的，未注册的阶段直接跳过。
+/// Wraps up to three C callbacks (Started/Response/Finished) into a regular
+/// [`Middleware`] so FFI callers get the same "observe before -> call next ->
+/// rewrite response -> wind down" lifecycle as a native Rust middleware. All
+/// three phases are optional; a phase that wasn't registered is simply skipped.
+struct PhasedMiddleware {
+    name: String,
+    started_fn: Option<HushMiddlewareStartedFn>,
+    response_fn: Option<HushMiddlewareResponseFn>,
+    finished_fn: Option<HushMiddlewareFinishedFn>,
+    user_data: Option<String>,
+}
+
+impl PhasedMiddleware {
+    fn user_data_ptr(&self) -> *mut c_char {
+        match &self.user_data {
+            Some(data) => match to_c_string(data) {
+                Ok(c_str) => c_str.into_raw(),
+                Err(_) => std::ptr::null_mut(),
+            },
+            None => std::ptr::null_mut(),
+        }
+    }
+
+    fn free_user_data_ptr(ptr: *mut c_char) {
+        if !ptr.is_null() {
+            unsafe {
+                let _ = CString::from_raw(ptr);
+            }
+        }
+    }
+
+    /// 调用响应阶段回调改写响应；回调失败则转换为软错误（`MiddlewareResult::Error`）
+    fn rewrite_response(&self, response_fn: HushMiddlewareResponseFn, mut response: ResponseContext) -> HushResult<MiddlewareResult> {
+        match response_context_to_c(&response) {
+            Ok((mut c_response, _c_strings)) => {
+                let user_data_ptr = self.user_data_ptr();
+                let outcome = response_fn(&mut c_response, user_data_ptr);
+                Self::free_user_data_ptr(user_data_ptr);
+
+                if outcome != 0 {
+                    return Ok(MiddlewareResult::Error(
+                        HushError::InternalError(format!("Middleware '{}' failed in response phase", self.name))
+                    ));
+                }
+
+                if let Err(e) = update_response_context_from_c(&mut response, &c_response) {
+                    return Ok(MiddlewareResult::Error(e));
+                }
+
+                Ok(MiddlewareResult::Response(response))
+            }
+            Err(e) => Ok(MiddlewareResult::Error(e)),
+        }
+    }
+}
+
+impl Middleware for PhasedMiddleware {
+    fn process(&self, context: &mut MiddlewareContext, next: NextFunction) -> HushResult<MiddlewareResult> {
+        let started_error = match self.started_fn {
+            Some(started) => match request_context_to_c(&mut context.request) {
+                Ok((mut c_context, _backing)) => {
+                    let user_data_ptr = self.user_data_ptr();
+                    let outcome = started(&mut c_context, user_data_ptr);
+                    Self::free_user_data_ptr(user_data_ptr);
+
+                    if outcome != 0 {
+                        Some(HushError::InternalError(format!("Middleware '{}' failed in started phase", self.name)))
+                    } else {
+                        update_request_context_from_c(&mut context.request, &c_context).err()
+                    }
+                }
+                Err(e) => Some(e),
+            },
+            None => None,
+        };
+
+        let result = match started_error {
+            Some(error) => Ok(MiddlewareResult::Error(error)),
+            None => match (next(context), self.response_fn) {
+                (Ok(MiddlewareResult::Response(response)), Some(response_fn)) => {
+                    self.rewrite_response(response_fn, response)
+                }
+                (other, _) => other,
+            },
+        };
+
+        if let Some(finished) = self.finished_fn {
+            let user_data_ptr = self.user_data_ptr();
+            finished(user_data_ptr);
+            Self::free_user_data_ptr(user_data_ptr);
+        }
+
+        result
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
 // ============================================================================
 // FFI 导出函数 | FFI Exported Functions
 // ============================================================================
 
+/// 读取请求头：直接从回调收到的 `HushRequestContext` 指针查询存活的 Rust
+/// `RequestContext`，因此总能看到本中间件执行之前的最新值，而不局限于调用
+/// 开始时拍下的 headers 快照数组。返回值是新分配的 C 字符串，调用方应当在
+/// 用完后像其它 Hush 字符串一样释放它；未找到对应 header 时返回空指针。
+/// Reads a request header straight from the live Rust `RequestContext` behind
+/// the `HushRequestContext` pointer a callback receives, so it always sees the
+/// latest value rather than being limited to the headers snapshot array taken
+/// when the call started. Returns a newly-allocated C string the caller should
+/// free like any other Hush string; returns null if the header isn't set.
+#[unsafe(no_mangle)]
+pub extern "C" fn hush_request_get_header(context: *const HushRequestContext, name: *const c_char) -> *const c_char {
+    if context.is_null() || name.is_null() {
+        set_last_error(HushError::NullPointer);
+        return std::ptr::null();
+    }
+
+    unsafe {
+        let c_context = &*context;
+        if c_context.request_ptr.is_null() {
+            set_last_error(HushError::NullPointer);
+            return std::ptr::null();
+        }
+
+        let name_str = match from_c_string(name) {
+            Ok(s) => s,
+            Err(e) => {
+                set_last_error(e);
+                return std::ptr::null();
+            }
+        };
+
+        let request = &*(c_context.request_ptr as *const RequestContext);
+        match request.get_header(&name_str) {
+            Some(value) => match to_c_string(value) {
+                Ok(c_str) => c_str.into_raw(),
+                Err(e) => {
+                    set_last_error(e);
+                    std::ptr::null()
+                }
+            },
+            None => std::ptr::null(),
+        }
+    }
+}
+
+/// 写入 user_data：直接修改回调收到的 `HushRequestContext` 背后存活的 Rust
+/// `RequestContext`，因此不受限于固定大小的 user_data 快照数组——可以写入
+/// 调用开始时并不存在的新键（例如认证中间件解析出用户 id 后写回供下游读取）。
+/// 成功返回 0，参数非法返回 -1。
+/// Writes a user_data entry directly into the live Rust `RequestContext` behind
+/// the `HushRequestContext` pointer, so unlike the fixed-size user_data
+/// snapshot array this can introduce a brand-new key that didn't exist when
+/// the call started (e.g. an auth middleware resolving a user id and handing
+/// it to downstream middleware/handlers). Returns 0 on success, -1 on bad input.
+#[unsafe(no_mangle)]
+pub extern "C" fn hush_request_set_user_data(context: *mut HushRequestContext, key: *const c_char, value: *const c_char) -> c_int {
+    if context.is_null() || key.is_null() || value.is_null() {
+        set_last_error(HushError::NullPointer);
+        return -1;
+    }
+
+    unsafe {
+        let c_context = &*context;
+        if c_context.request_ptr.is_null() {
+            set_last_error(HushError::NullPointer);
+            return -1;
+        }
+
+        let key_str = match from_c_string(key) {
+            Ok(s) => s,
+            Err(e) => {
+                set_last_error(e);
+                return -1;
+            }
+        };
+        let value_str = match from_c_string(value) {
+            Ok(s) => s,
+            Err(e) => {
+                set_last_error(e);
+                return -1;
+            }
+        };
+
+        let request = &mut *(c_context.request_ptr as *mut RequestContext);
+        request.set_user_data(key_str, value_str);
+        0
+    }
+}
+
+/// 读取一个会话字段：和 `hush_request_get_header` 一样直接查询回调收到的
+/// `HushRequestContext` 背后存活的 Rust `RequestContext`，但读取的是
+/// `session.` 前缀下的会话数据，而不是请求头。未设置该字段时返回空指针。
+/// Reads a session field: like `hush_request_get_header`, queries the live
+/// Rust `RequestContext` behind the `HushRequestContext` pointer directly,
+/// but reads the `session.`-prefixed session data rather than a request
+/// header. Returns null if the field isn't set.
+#[unsafe(no_mangle)]
+pub extern "C" fn hush_session_get_value(context: *const HushRequestContext, key: *const c_char) -> *const c_char {
+    if context.is_null() || key.is_null() {
+        set_last_error(HushError::NullPointer);
+        return std::ptr::null();
+    }
+
+    unsafe {
+        let c_context = &*context;
+        if c_context.request_ptr.is_null() {
+            set_last_error(HushError::NullPointer);
+            return std::ptr::null();
+        }
+
+        let key_str = match from_c_string(key) {
+            Ok(s) => s,
+            Err(e) => {
+                set_last_error(e);
+                return std::ptr::null();
+            }
+        };
+
+        let request = &*(c_context.request_ptr as *const RequestContext);
+        match request.get_user_data(&format!("{}{}", SESSION_DATA_PREFIX, key_str)) {
+            Some(value) => match to_c_string(value) {
+                Ok(c_str) => c_str.into_raw(),
+                Err(e) => {
+                    set_last_error(e);
+                    std::ptr::null()
+                }
+            },
+            None => std::ptr::null(),
+        }
+    }
+}
+
+/// 写入一个会话字段：和 `hush_request_set_user_data` 一样直接写入回调收到的
+/// `HushRequestContext` 背后存活的 Rust `RequestContext`，但会自动加上
+/// `session.` 前缀，使这个值成为会话数据的一部分，在响应阶段被会话中间件
+/// 重新编码进 `Set-Cookie`。成功返回 0，参数非法返回 -1。
+/// Writes a session field: like `hush_request_set_user_data`, writes
+/// directly into the live Rust `RequestContext` behind the
+/// `HushRequestContext` pointer, but automatically prefixes the key with
+/// `session.`, making the value part of the session data that the session
+/// middleware re-encodes into `Set-Cookie` in the response phase. Returns 0
+/// on success, -1 on bad input.
+#[unsafe(no_mangle)]
+pub extern "C" fn hush_session_set_value(context: *mut HushRequestContext, key: *const c_char, value: *const c_char) -> c_int {
+    if context.is_null() || key.is_null() || value.is_null() {
+        set_last_error(HushError::NullPointer);
+        return -1;
+    }
+
+    unsafe {
+        let c_context = &*context;
+        if c_context.request_ptr.is_null() {
+            set_last_error(HushError::NullPointer);
+            return -1;
+        }
+
+        let key_str = match from_c_string(key) {
+            Ok(s) => s,
+            Err(e) => {
+                set_last_error(e);
+                return -1;
+            }
+        };
+        let value_str = match from_c_string(value) {
+            Ok(s) => s,
+            Err(e) => {
+                set_last_error(e);
+                return -1;
+            }
+        };
+
+        let request = &mut *(c_context.request_ptr as *mut RequestContext);
+        request.set_user_data(format!("{}{}", SESSION_DATA_PREFIX, key_str), value_str);
+        0
+    }
+}
+
 /// 创建新的中间件链
 #[unsafe(no_mangle)]
 pub extern "C" fn hush_middleware_new() -> *mut HushMiddleware {
@@ -134,8 +608,8 @@ pub extern "C" fn hush_middleware_add(
             // 添加函数式中间件
             chain.add_function(middleware_name, move |ctx, next| {
                 // 将 Rust 上下文转换为 C 结构
-                match request_context_to_c(&ctx.request) {
-                    Ok((mut c_context, _c_strings)) => {
+                match request_context_to_c(&mut ctx.request) {
+                    Ok((mut c_context, _backing)) => {
                         // 准备 user_data 参数
                         let user_data_ptr = if let Some(ref data) = user_data_string {
                             match to_c_string(data) {
@@ -214,13 +688,108 @@ pub extern "C" fn hush_middleware_add_cors(
         
         if let Ok(mut chain) = middleware_ref.chain.lock() {
             let cors_middleware = CorsMiddleware::new(origins);
-            chain.add(cors_middleware);
+            add_cors_configurable(&mut chain, cors_middleware);
+        } else {
+            set_last_error(HushError::InternalError("Failed to lock middleware chain".to_string()));
+        }
+    }
+}
+
+/// 添加可完整配置的 CORS 中间件：除来源外，还可指定允许的方法、允许的头、
+/// 是否允许凭据，以及预检结果的缓存时长（秒）。任意字符串参数传 null 均沿用
+/// `CorsMiddleware::new` 的默认值。
+/// Add a fully-configurable CORS middleware: beyond the origin list, also
+/// lets the caller set the allowed methods, allowed headers, whether
+/// credentials are allowed, and the preflight cache lifetime (seconds). Any
+/// null string argument falls back to `CorsMiddleware::new`'s defaults.
+#[unsafe(no_mangle)]
+pub extern "C" fn hush_middleware_add_cors_config(
+    middleware: *mut HushMiddleware,
+    allowed_origins: *const c_char,
+    allowed_methods: *const c_char,
+    allowed_headers: *const c_char,
+    allow_credentials: c_int,
+    max_age_secs: u32,
+) {
+    if middleware.is_null() {
+        set_last_error(HushError::NullPointer);
+        return;
+    }
+
+    unsafe {
+        let middleware_ref = &*middleware;
+
+        let origins = if allowed_origins.is_null() {
+            "*".to_string()
+        } else {
+            match from_c_string(allowed_origins) {
+                Ok(s) => s,
+                Err(e) => {
+                    set_last_error(e);
+                    return;
+                }
+            }
+        };
+
+        let mut cors_middleware = CorsMiddleware::new(origins);
+
+        if !allowed_methods.is_null() {
+            match from_c_string(allowed_methods) {
+                Ok(s) => cors_middleware = cors_middleware.with_methods(s),
+                Err(e) => {
+                    set_last_error(e);
+                    return;
+                }
+            }
+        }
+
+        if !allowed_headers.is_null() {
+            match from_c_string(allowed_headers) {
+                Ok(s) => cors_middleware = cors_middleware.with_headers(s),
+                Err(e) => {
+                    set_last_error(e);
+                    return;
+                }
+            }
+        }
+
+        if max_age_secs > 0 {
+            cors_middleware = cors_middleware.with_max_age(max_age_secs);
+        }
+
+        if allow_credentials != 0 {
+            cors_middleware = cors_middleware.with_credentials();
+        }
+
+        let cors_middleware = match cors_middleware.finish() {
+            Ok(middleware) => middleware,
+            Err(e) => {
+                set_last_error(e);
+                return;
+            }
+        };
+
+        if let Ok(mut chain) = middleware_ref.chain.lock() {
+            add_cors_configurable(&mut chain, cors_middleware);
         } else {
             set_last_error(HushError::InternalError("Failed to lock middleware chain".to_string()));
         }
     }
 }
 
+/// 把一个已经构造好的 `CorsMiddleware` 作为可热重载的中间件注册进链，
+/// 供 `hush_middleware_configure` 后续用新 JSON 替换其配置
+/// Registers an already-built `CorsMiddleware` as a hot-reloadable middleware,
+/// so `hush_middleware_configure` can later replace its config with new JSON
+fn add_cors_configurable(chain: &mut MiddlewareChain, cors_middleware: CorsMiddleware) {
+    let config_json = cors_middleware.to_config_json();
+    let params = CorsMiddleware::param_spec();
+    chain.add_configurable(cors_middleware, config_json, params, |config_json| {
+        CorsMiddleware::from_config_json(config_json)
+            .map(|m| Arc::new(m) as Arc<dyn Middleware>)
+    });
+}
+
 /// 添加 JWT 认证中间件
 #[unsafe(no_mangle)]
 pub extern "C" fn hush_middleware_add_auth_jwt(
@@ -252,64 +821,245 @@ pub extern "C" fn hush_middleware_add_auth_jwt(
     }
 }
 
-/// 添加日志中间件
+/// 添加日志中间件
+#[unsafe(no_mangle)]
+pub extern "C" fn hush_middleware_add_logger(middleware: *mut HushMiddleware) {
+    if middleware.is_null() {
+        set_last_error(HushError::NullPointer);
+        return;
+    }
+    
+    unsafe {
+        let middleware_ref = &*middleware;
+        
+        if let Ok(mut chain) = middleware_ref.chain.lock() {
+            let logger_middleware = LoggerMiddleware::new();
+            chain.add(logger_middleware);
+        } else {
+            set_last_error(HushError::InternalError("Failed to lock middleware chain".to_string()));
+        }
+    }
+}
+
+/// 添加 CSRF 保护中间件（双重提交 Cookie 模式）
+#[unsafe(no_mangle)]
+pub extern "C" fn hush_middleware_add_csrf(
+    middleware: *mut HushMiddleware,
+    cookie_name: *const c_char,
+    header_name: *const c_char,
+) {
+    if middleware.is_null() || cookie_name.is_null() || header_name.is_null() {
+        set_last_error(HushError::NullPointer);
+        return;
+    }
+
+    unsafe {
+        let middleware_ref = &*middleware;
+
+        let cookie_name_str = match from_c_string(cookie_name) {
+            Ok(s) => s,
+            Err(e) => {
+                set_last_error(e);
+                return;
+            }
+        };
+
+        let header_name_str = match from_c_string(header_name) {
+            Ok(s) => s,
+            Err(e) => {
+                set_last_error(e);
+                return;
+            }
+        };
+
+        if let Ok(mut chain) = middleware_ref.chain.lock() {
+            let csrf_middleware = CsrfMiddleware::new(cookie_name_str, header_name_str);
+            chain.add(csrf_middleware);
+        } else {
+            set_last_error(HushError::InternalError("Failed to lock middleware chain".to_string()));
+        }
+    }
+}
+
+/// 添加一个三阶段（Started/Response/Finished）中间件。三个回调都是可选的
+/// （传 `None`/空函数指针即可跳过该阶段），`user_data` 会在每次回调前转换为
+/// C 字符串指针、回调返回后立即释放，和 [`hush_middleware_add`] 的约定一致。
+/// Adds a three-phase (Started/Response/Finished) middleware. All three
+/// callbacks are optional (pass a null function pointer to skip a phase);
+/// `user_data` is converted to a C string pointer before each callback and
+/// freed right after, matching [`hush_middleware_add`]'s convention.
+#[unsafe(no_mangle)]
+pub extern "C" fn hush_middleware_add_phased(
+    middleware: *mut HushMiddleware,
+    started_fn: Option<HushMiddlewareStartedFn>,
+    response_fn: Option<HushMiddlewareResponseFn>,
+    finished_fn: Option<HushMiddlewareFinishedFn>,
+    user_data: *mut c_char,
+) {
+    if middleware.is_null() {
+        set_last_error(HushError::NullPointer);
+        return;
+    }
+
+    unsafe {
+        let middleware_ref = &*middleware;
+
+        let user_data_string = if user_data.is_null() {
+            None
+        } else {
+            from_c_string(user_data).ok()
+        };
+
+        if let Ok(mut chain) = middleware_ref.chain.lock() {
+            let name = format!("phased_middleware_{}", chain.len());
+            chain.add(PhasedMiddleware {
+                name,
+                started_fn,
+                response_fn,
+                finished_fn,
+                user_data: user_data_string,
+            });
+        } else {
+            set_last_error(HushError::InternalError("Failed to lock middleware chain".to_string()));
+        }
+    }
+}
+
+/// 添加基于IP的请求限流中间件
+#[unsafe(no_mangle)]
+pub extern "C" fn hush_middleware_add_rate_limit(
+    middleware: *mut HushMiddleware,
+    max_requests: u32,
+    window_seconds: u64,
+) {
+    if middleware.is_null() {
+        set_last_error(HushError::NullPointer);
+        return;
+    }
+    
+    unsafe {
+        let middleware_ref = &*middleware;
+        
+        if let Ok(mut chain) = middleware_ref.chain.lock() {
+            let rate_limit_middleware = RateLimitMiddleware::new(max_requests, window_seconds);
+            add_rate_limit_configurable(&mut chain, rate_limit_middleware);
+        } else {
+            set_last_error(HushError::InternalError("Failed to lock middleware chain".to_string()));
+        }
+    }
+}
+
+/// 添加基于用户ID的请求限流中间件
 #[unsafe(no_mangle)]
-pub extern "C" fn hush_middleware_add_logger(middleware: *mut HushMiddleware) {
+pub extern "C" fn hush_middleware_add_rate_limit_by_user(middleware: *mut HushMiddleware) {
     if middleware.is_null() {
         set_last_error(HushError::NullPointer);
         return;
     }
-    
+
     unsafe {
         let middleware_ref = &*middleware;
-        
+
         if let Ok(mut chain) = middleware_ref.chain.lock() {
-            let logger_middleware = LoggerMiddleware::new();
-            chain.add(logger_middleware);
+            let rate_limit_middleware = RateLimitMiddleware::by_user_id();
+            add_rate_limit_configurable(&mut chain, rate_limit_middleware);
         } else {
             set_last_error(HushError::InternalError("Failed to lock middleware chain".to_string()));
         }
     }
 }
 
-/// 添加基于IP的请求限流中间件
+/// 把一个已经构造好的 `RateLimitMiddleware` 作为可热重载的中间件注册进链，
+/// 供 `hush_middleware_configure` 后续用新 JSON 替换其阈值
+/// Registers an already-built `RateLimitMiddleware` as a hot-reloadable
+/// middleware, so `hush_middleware_configure` can later replace its
+/// thresholds with new JSON
+fn add_rate_limit_configurable(chain: &mut MiddlewareChain, rate_limit_middleware: RateLimitMiddleware) {
+    let config_json = rate_limit_middleware.to_config_json();
+    let params = RateLimitMiddleware::param_spec();
+    chain.add_configurable(rate_limit_middleware, config_json, params, |config_json| {
+        RateLimitMiddleware::from_config_json(config_json)
+            .map(|m| Arc::new(m) as Arc<dyn Middleware>)
+    });
+}
+
+/// 添加请求超时中间件：剩余链条（含路由处理器）的执行超过 `timeout_ms`
+/// 毫秒仍未返回时，短路返回 `408 Request Timeout`
+/// Add a request-timeout middleware: if the remainder of the chain
+/// (including the route handler) doesn't return within `timeout_ms`
+/// milliseconds, short-circuit with `408 Request Timeout`
 #[unsafe(no_mangle)]
-pub extern "C" fn hush_middleware_add_rate_limit(
-    middleware: *mut HushMiddleware,
-    max_requests: u32,
-    window_seconds: u64,
-) {
+pub extern "C" fn hush_middleware_add_timeout(middleware: *mut HushMiddleware, timeout_ms: u64) {
     if middleware.is_null() {
         set_last_error(HushError::NullPointer);
         return;
     }
-    
+
     unsafe {
         let middleware_ref = &*middleware;
-        
+
         if let Ok(mut chain) = middleware_ref.chain.lock() {
-            let rate_limit_middleware = RateLimitMiddleware::new(max_requests, window_seconds);
-            chain.add(rate_limit_middleware);
+            chain.add(TimeoutMiddleware::new(timeout_ms));
         } else {
             set_last_error(HushError::InternalError("Failed to lock middleware chain".to_string()));
         }
     }
 }
 
-/// 添加基于用户ID的请求限流中间件
+/// 添加会话中间件：`secret` 非空时使用签名 cookie 后端（无需服务端存储，
+/// 会话数据整体编码进 cookie 并签名防篡改），为 null 时使用进程内存储后端
+/// （cookie 只携带一个 id，数据留在内存里）。`cookie_name` 为 null 时使用
+/// 默认的 "session_id"；`max_age_secs` 为 0 时不设置 `Max-Age`（会话 cookie）。
+/// Adds a session middleware: if `secret` is non-null, uses the
+/// signed-cookie backend (no server-side storage needed, the session data is
+/// encoded into the cookie itself and signed against tampering); if null,
+/// uses the in-memory-store backend (the cookie only carries an id, the data
+/// stays in memory). A null `cookie_name` falls back to the default
+/// `"session_id"`; `max_age_secs` of 0 omits `Max-Age` (a session-lifetime
+/// cookie).
 #[unsafe(no_mangle)]
-pub extern "C" fn hush_middleware_add_rate_limit_by_user(middleware: *mut HushMiddleware) {
+pub extern "C" fn hush_middleware_add_session(
+    middleware: *mut HushMiddleware,
+    secret: *const c_char,
+    cookie_name: *const c_char,
+    max_age_secs: u64,
+) {
     if middleware.is_null() {
         set_last_error(HushError::NullPointer);
         return;
     }
-    
+
     unsafe {
         let middleware_ref = &*middleware;
-        
+
+        let mut session_middleware = if secret.is_null() {
+            SessionMiddleware::new(Arc::new(InMemorySessionStore::new()))
+        } else {
+            match from_c_string(secret) {
+                Ok(s) => SessionMiddleware::with_signed_cookie(s),
+                Err(e) => {
+                    set_last_error(e);
+                    return;
+                }
+            }
+        };
+
+        if !cookie_name.is_null() {
+            match from_c_string(cookie_name) {
+                Ok(s) => session_middleware = session_middleware.with_cookie_name(&s),
+                Err(e) => {
+                    set_last_error(e);
+                    return;
+                }
+            }
+        }
+
+        if max_age_secs > 0 {
+            session_middleware = session_middleware.with_max_age(max_age_secs);
+        }
+
         if let Ok(mut chain) = middleware_ref.chain.lock() {
-            let rate_limit_middleware = RateLimitMiddleware::by_user_id();
-            chain.add(rate_limit_middleware);
+            chain.add(session_middleware);
         } else {
             set_last_error(HushError::InternalError("Failed to lock middleware chain".to_string()));
         }
@@ -449,9 +1199,8 @@ pub extern "C" fn hush_middleware_names(middleware: *mut HushMiddleware) -> *con
         let middleware_ref = &*middleware;
         
         if let Ok(chain) = middleware_ref.chain.lock() {
-            let names = chain.middleware_names();
-            let json_str = format!("{:?}", names); // 简化的 JSON 格式
-            
+            let json_str = chain.registry_json();
+
             match to_c_string(&json_str) {
                 Ok(c_str) => c_str.into_raw(),
                 Err(e) => {
@@ -466,11 +1215,66 @@ pub extern "C" fn hush_middleware_names(middleware: *mut HushMiddleware) -> *con
     }
 }
 
+/// 用新的 JSON 配置热重载名字匹配的中间件，不重建整条链。目标中间件必须是
+/// 通过支持热配置的构造函数添加的（目前是 `hush_middleware_add_cors[_config]`
+/// 和 `hush_middleware_add_rate_limit[_by_user]`），否则返回 -1 并设置错误。
+/// Hot-reloads the name-matching middleware with new JSON config, without
+/// rebuilding the whole chain. The target middleware must have been added via
+/// a constructor that supports hot configuration (currently
+/// `hush_middleware_add_cors[_config]` and
+/// `hush_middleware_add_rate_limit[_by_user]`), otherwise returns -1 and sets
+/// the last error.
+#[unsafe(no_mangle)]
+pub extern "C" fn hush_middleware_configure(
+    middleware: *mut HushMiddleware,
+    name: *const c_char,
+    config_json: *const c_char,
+) -> c_int {
+    if middleware.is_null() || name.is_null() || config_json.is_null() {
+        set_last_error(HushError::NullPointer);
+        return -1;
+    }
+
+    unsafe {
+        let middleware_ref = &*middleware;
+
+        let name_str = match from_c_string(name) {
+            Ok(s) => s,
+            Err(e) => {
+                set_last_error(e);
+                return -1;
+            }
+        };
+
+        let config_str = match from_c_string(config_json) {
+            Ok(s) => s,
+            Err(e) => {
+                set_last_error(e);
+                return -1;
+            }
+        };
+
+        if let Ok(mut chain) = middleware_ref.chain.lock() {
+            match chain.configure(&name_str, &config_str) {
+                Ok(()) => 0,
+                Err(e) => {
+                    set_last_error(e);
+                    -1
+                }
+            }
+        } else {
+            set_last_error(HushError::InternalError("Failed to lock middleware chain".to_string()));
+            -1
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::ffi::CString;
-    
+    use std::sync::atomic::AtomicUsize;
+
     #[test]
     fn test_middleware_ffi_basic() {
         // 创建中间件链
@@ -501,10 +1305,47 @@ mod tests {
         
         let count = hush_middleware_count(middleware);
         assert_eq!(count, 1);
-        
+
         hush_middleware_free(middleware);
     }
-    
+
+    #[test]
+    fn test_cors_middleware_add_config_ffi() {
+        let middleware = hush_middleware_new();
+        assert!(!middleware.is_null());
+
+        let origins = CString::new("https://a.example.com, https://b.example.com").unwrap();
+        let methods = CString::new("GET, POST").unwrap();
+        let headers = CString::new("Content-Type").unwrap();
+        hush_middleware_add_cors_config(middleware, origins.as_ptr(), methods.as_ptr(), headers.as_ptr(), 1, 3600);
+
+        let count = hush_middleware_count(middleware);
+        assert_eq!(count, 1);
+
+        hush_middleware_free(middleware);
+    }
+
+    #[test]
+    fn test_cors_middleware_add_config_ffi_defaults_with_null_args() {
+        let middleware = hush_middleware_new();
+        assert!(!middleware.is_null());
+
+        let origins = CString::new("*").unwrap();
+        hush_middleware_add_cors_config(
+            middleware,
+            origins.as_ptr(),
+            std::ptr::null(),
+            std::ptr::null(),
+            0,
+            0,
+        );
+
+        let count = hush_middleware_count(middleware);
+        assert_eq!(count, 1);
+
+        hush_middleware_free(middleware);
+    }
+
     #[test]
     fn test_auth_middleware_ffi() {
         let middleware = hush_middleware_new();
@@ -519,6 +1360,92 @@ mod tests {
         hush_middleware_free(middleware);
     }
     
+    #[test]
+    fn test_csrf_middleware_ffi() {
+        let middleware = hush_middleware_new();
+        assert!(!middleware.is_null());
+
+        let cookie_name = CString::new("csrf_token").unwrap();
+        let header_name = CString::new("X-CSRF-Token").unwrap();
+        hush_middleware_add_csrf(middleware, cookie_name.as_ptr(), header_name.as_ptr());
+
+        let count = hush_middleware_count(middleware);
+        assert_eq!(count, 1);
+
+        hush_middleware_free(middleware);
+    }
+
+    static PHASED_STARTED_CALLS: AtomicUsize = AtomicUsize::new(0);
+    static PHASED_RESPONSE_CALLS: AtomicUsize = AtomicUsize::new(0);
+    static PHASED_FINISHED_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    extern "C" fn counting_started_fn(_ctx: *mut HushRequestContext, _user_data: *mut c_char) -> c_int {
+        PHASED_STARTED_CALLS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        0
+    }
+
+    extern "C" fn rewriting_response_fn(ctx: *mut HushResponseContext, _user_data: *mut c_char) -> c_int {
+        PHASED_RESPONSE_CALLS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        unsafe {
+            (*ctx).status_code = 201;
+        }
+        0
+    }
+
+    extern "C" fn counting_finished_fn(_user_data: *mut c_char) {
+        PHASED_FINISHED_CALLS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    extern "C" fn short_circuit_handler(_ctx: *mut HushRequestContext, _user_data: *mut c_char) -> c_int {
+        1
+    }
+
+    #[test]
+    fn test_phased_middleware_ffi_registers() {
+        let middleware = hush_middleware_new();
+        assert!(!middleware.is_null());
+
+        hush_middleware_add_phased(middleware, None, None, None, std::ptr::null_mut());
+
+        let count = hush_middleware_count(middleware);
+        assert_eq!(count, 1);
+
+        hush_middleware_free(middleware);
+    }
+
+    #[test]
+    fn test_phased_middleware_runs_all_three_phases_and_rewrites_response() {
+        let middleware = hush_middleware_new();
+        assert!(!middleware.is_null());
+
+        hush_middleware_add_phased(
+            middleware,
+            Some(counting_started_fn),
+            Some(rewriting_response_fn),
+            Some(counting_finished_fn),
+            std::ptr::null_mut(),
+        );
+        hush_middleware_add(middleware, short_circuit_handler, std::ptr::null_mut());
+
+        let started_before = PHASED_STARTED_CALLS.load(std::sync::atomic::Ordering::SeqCst);
+        let response_before = PHASED_RESPONSE_CALLS.load(std::sync::atomic::Ordering::SeqCst);
+        let finished_before = PHASED_FINISHED_CALLS.load(std::sync::atomic::Ordering::SeqCst);
+
+        let method = CString::new("GET").unwrap();
+        let path = CString::new("/phased").unwrap();
+        let result = hush_middleware_execute(middleware, method.as_ptr(), path.as_ptr(), std::ptr::null());
+        assert!(!result.is_null());
+        unsafe {
+            let _ = CString::from_raw(result as *mut c_char);
+        }
+
+        assert_eq!(PHASED_STARTED_CALLS.load(std::sync::atomic::Ordering::SeqCst), started_before + 1);
+        assert_eq!(PHASED_RESPONSE_CALLS.load(std::sync::atomic::Ordering::SeqCst), response_before + 1);
+        assert_eq!(PHASED_FINISHED_CALLS.load(std::sync::atomic::Ordering::SeqCst), finished_before + 1);
+
+        hush_middleware_free(middleware);
+    }
+
     #[test]
     fn test_rate_limit_middleware_ffi() {
         let middleware = hush_middleware_new();
@@ -543,10 +1470,113 @@ mod tests {
         
         let count = hush_middleware_count(middleware);
         assert_eq!(count, 1);
-        
+
         hush_middleware_free(middleware);
     }
-    
+
+    #[test]
+    fn test_hush_middleware_configure_hot_reloads_cors_origins() {
+        let middleware = hush_middleware_new();
+        assert!(!middleware.is_null());
+
+        let origins = CString::new("https://a.example.com").unwrap();
+        hush_middleware_add_cors(middleware, origins.as_ptr());
+
+        let name = CString::new("cors").unwrap();
+        let new_config = CString::new(r#"{"allowed_origins":"https://b.example.com"}"#).unwrap();
+        let result = hush_middleware_configure(middleware, name.as_ptr(), new_config.as_ptr());
+        assert_eq!(result, 0);
+
+        let names_ptr = hush_middleware_names(middleware);
+        assert!(!names_ptr.is_null());
+        let names_json = unsafe { from_c_string(names_ptr).unwrap() };
+        unsafe { let _ = CString::from_raw(names_ptr as *mut c_char); }
+        assert!(names_json.contains("https://b.example.com"));
+        assert!(!names_json.contains("https://a.example.com"));
+
+        hush_middleware_free(middleware);
+    }
+
+    #[test]
+    fn test_hush_middleware_configure_rejects_unknown_name() {
+        let middleware = hush_middleware_new();
+        assert!(!middleware.is_null());
+
+        hush_middleware_add_rate_limit(middleware, 10, 60);
+
+        let name = CString::new("does_not_exist").unwrap();
+        let config = CString::new(r#"{"max_requests":5,"window_seconds":30}"#).unwrap();
+        let result = hush_middleware_configure(middleware, name.as_ptr(), config.as_ptr());
+        assert_eq!(result, -1);
+
+        hush_middleware_free(middleware);
+    }
+
+    #[test]
+    fn test_timeout_middleware_ffi() {
+        let middleware = hush_middleware_new();
+        assert!(!middleware.is_null());
+
+        hush_middleware_add_timeout(middleware, 500);
+
+        let count = hush_middleware_count(middleware);
+        assert_eq!(count, 1);
+
+        hush_middleware_free(middleware);
+    }
+
+    #[test]
+    fn test_session_middleware_ffi_in_memory_default() {
+        let middleware = hush_middleware_new();
+        assert!(!middleware.is_null());
+
+        hush_middleware_add_session(middleware, std::ptr::null(), std::ptr::null(), 0);
+
+        let count = hush_middleware_count(middleware);
+        assert_eq!(count, 1);
+
+        hush_middleware_free(middleware);
+    }
+
+    #[test]
+    fn test_session_middleware_ffi_signed_cookie() {
+        let middleware = hush_middleware_new();
+        assert!(!middleware.is_null());
+
+        let secret = CString::new("s3cr3t").unwrap();
+        let cookie_name = CString::new("sid").unwrap();
+        hush_middleware_add_session(middleware, secret.as_ptr(), cookie_name.as_ptr(), 3600);
+
+        let count = hush_middleware_count(middleware);
+        assert_eq!(count, 1);
+
+        hush_middleware_free(middleware);
+    }
+
+    #[test]
+    fn test_hush_session_get_and_set_value_operate_on_live_context() {
+        let mut request = RequestContext::new(HttpMethod::GET, "/profile".to_string());
+        request.set_user_data("session.user".to_string(), "ada".to_string());
+
+        let (mut c_context, _backing) = request_context_to_c(&mut request).unwrap();
+
+        let key = CString::new("user").unwrap();
+        let value_ptr = hush_session_get_value(&c_context, key.as_ptr());
+        assert!(!value_ptr.is_null());
+        let value = unsafe { from_c_string(value_ptr).unwrap() };
+        assert_eq!(value, "ada");
+        unsafe {
+            let _ = CString::from_raw(value_ptr as *mut c_char);
+        }
+
+        let new_key = CString::new("cart_count").unwrap();
+        let new_value = CString::new("3").unwrap();
+        let outcome = hush_session_set_value(&mut c_context, new_key.as_ptr(), new_value.as_ptr());
+        assert_eq!(outcome, 0);
+
+        assert_eq!(request.get_user_data("session.cart_count"), Some(&"3".to_string()));
+    }
+
     #[test]
     fn test_multiple_middleware_ffi() {
         let middleware = hush_middleware_new();
@@ -579,4 +1609,83 @@ mod tests {
         
         hush_middleware_free(middleware);
     }
+
+    #[test]
+    fn test_request_context_to_c_populates_header_and_user_data_arrays() {
+        let mut request = RequestContext::new(HttpMethod::GET, "/profile".to_string());
+        request.add_header("X-Trace".to_string(), "abc123".to_string());
+        request.set_user_data("user_id".to_string(), "42".to_string());
+
+        let (c_context, _backing) = request_context_to_c(&mut request).unwrap();
+        assert_eq!(c_context.headers_count, 1);
+        assert_eq!(c_context.user_data_count, 1);
+
+        unsafe {
+            let header_key = from_c_string(*c_context.headers_keys).unwrap();
+            let header_value = from_c_string(*c_context.headers_values).unwrap();
+            assert_eq!(header_key, "X-Trace");
+            assert_eq!(header_value, "abc123");
+
+            let user_data_key = from_c_string(*c_context.user_data_keys).unwrap();
+            let user_data_value = from_c_string(*c_context.user_data_values).unwrap();
+            assert_eq!(user_data_key, "user_id");
+            assert_eq!(user_data_value, "42");
+        }
+    }
+
+    #[test]
+    fn test_update_request_context_from_c_merges_modified_arrays_and_body() {
+        let mut request = RequestContext::new(HttpMethod::GET, "/profile".to_string());
+
+        let header_key = CString::new("X-Auth-User").unwrap();
+        let header_value = CString::new("alice").unwrap();
+        let header_keys = [header_key.as_ptr()];
+        let header_values = [header_value.as_ptr()];
+
+        let body = CString::new("updated body").unwrap();
+
+        let c_context = HushRequestContext {
+            method: std::ptr::null(),
+            path: std::ptr::null(),
+            body: body.as_ptr(),
+            body_length: 0,
+            headers_count: 1,
+            headers_keys: header_keys.as_ptr(),
+            headers_values: header_values.as_ptr(),
+            user_data_count: 0,
+            user_data_keys: std::ptr::null(),
+            user_data_values: std::ptr::null(),
+            request_ptr: std::ptr::null_mut(),
+        };
+
+        update_request_context_from_c(&mut request, &c_context).unwrap();
+
+        assert_eq!(request.get_header("X-Auth-User"), Some(&"alice".to_string()));
+        assert_eq!(request.body_as_string().unwrap(), "updated body");
+    }
+
+    #[test]
+    fn test_hush_request_get_header_and_set_user_data_operate_on_live_context() {
+        let mut request = RequestContext::new(HttpMethod::GET, "/profile".to_string());
+        request.add_header("X-Trace".to_string(), "abc123".to_string());
+
+        let (mut c_context, _backing) = request_context_to_c(&mut request).unwrap();
+
+        let name = CString::new("X-Trace").unwrap();
+        let value_ptr = hush_request_get_header(&c_context, name.as_ptr());
+        assert!(!value_ptr.is_null());
+        let value = from_c_string(value_ptr).unwrap();
+        assert_eq!(value, "abc123");
+        unsafe {
+            let _ = CString::from_raw(value_ptr as *mut c_char);
+        }
+
+        let key = CString::new("user_id").unwrap();
+        let new_value = CString::new("42").unwrap();
+        let outcome = hush_request_set_user_data(&mut c_context, key.as_ptr(), new_value.as_ptr());
+        assert_eq!(outcome, 0);
+
+        // `request_ptr` 指回同一个活的 RequestContext，因此写入立即可见
+        assert_eq!(request.get_user_data("user_id"), Some(&"42".to_string()));
+    }
 }
\ No newline at end of file
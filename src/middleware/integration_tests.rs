@@ -68,24 +68,27 @@ mod tests {
         let auth = AuthMiddleware::new("secret_key".to_string())
             .with_skip_paths(vec!["/health".to_string(), "/public".to_string()])
             .with_header_name("X-Auth-Token".to_string());
-        
+        let valid_token = auth.issue_token(&std::collections::HashMap::from([
+            ("user_id".to_string(), "alice".to_string()),
+        ]));
+
         chain.add(auth);
-        
+
         // 测试跳过路径
         let request = RequestContext::new(HttpMethod::GET, "/health".to_string());
         let context = MiddlewareContext::new(request);
         let result = chain.execute(context);
         assert!(result.is_ok());
-        
+
         // 测试需要认证的路径（无令牌）
         let request = RequestContext::new(HttpMethod::GET, "/protected".to_string());
         let context = MiddlewareContext::new(request);
         let result = chain.execute(context).unwrap();
         assert_eq!(result.status.as_u16(), 401);
-        
+
         // 测试需要认证的路径（有效令牌）
         let mut request = RequestContext::new(HttpMethod::GET, "/protected".to_string());
-        request.add_header("X-Auth-Token".to_string(), "valid_token_12345".to_string());
+        request.add_header("X-Auth-Token".to_string(), valid_token);
         let context = MiddlewareContext::new(request);
         let result = chain.execute(context);
         assert!(result.is_ok());
@@ -137,9 +140,13 @@ mod tests {
         chain.add(LoggerMiddleware::new());                                    // 优先级 5
         chain.add(CorsMiddleware::permissive());                              // 优先级 10
         chain.add(RateLimitMiddleware::new(100, 3600));                       // 优先级 15
-        chain.add(AuthMiddleware::new("secret".to_string())                   // 优先级 20
-            .with_skip_paths(vec!["/health".to_string()]));
-        
+        let auth = AuthMiddleware::new("secret".to_string())                   // 优先级 20
+            .with_skip_paths(vec!["/health".to_string()]);
+        let valid_token = auth.issue_token(&std::collections::HashMap::from([
+            ("user_id".to_string(), "alice".to_string()),
+        ]));
+        chain.add(auth);
+
         assert_eq!(chain.len(), 4);
         
         // 验证中间件按优先级排序
@@ -160,7 +167,7 @@ mod tests {
         // 测试需要认证的请求
         let mut request = RequestContext::new(HttpMethod::POST, "/api/secure".to_string());
         request.add_header("Origin".to_string(), "https://example.com".to_string());
-        request.add_header("Authorization".to_string(), "Bearer valid_token_12345".to_string());
+        request.add_header("Authorization".to_string(), format!("Bearer {}", valid_token));
         request.set_body(r#"{"action": "create"}"#.as_bytes().to_vec());
         
         let context = MiddlewareContext::new(request);
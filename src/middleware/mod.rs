@@ -5,6 +5,8 @@
 pub mod core;
 pub mod ffi;
 pub mod builtin;
+pub mod session;
+pub mod registry;
 
 #[cfg(test)]
 mod integration_tests;
@@ -15,9 +17,19 @@ pub use core::{
     MiddlewareHandler, NextFunction
 };
 pub use ffi::{
-    HushMiddleware, HushMiddlewareHandler, HushRequestContext,
+    HushMiddleware, HushMiddlewareHandler, HushRequestContext, HushResponseContext,
+    HushMiddlewareStartedFn, HushMiddlewareResponseFn, HushMiddlewareFinishedFn,
     hush_middleware_new, hush_middleware_add, hush_middleware_free,
-    hush_middleware_add_cors, hush_middleware_add_auth_jwt, hush_middleware_add_logger,
-    hush_middleware_add_rate_limit, hush_middleware_add_rate_limit_by_user
+    hush_middleware_add_cors, hush_middleware_add_cors_config, hush_middleware_add_auth_jwt, hush_middleware_add_logger,
+    hush_middleware_add_rate_limit, hush_middleware_add_rate_limit_by_user,
+    hush_middleware_add_csrf, hush_middleware_add_phased,
+    hush_request_get_header, hush_request_set_user_data,
+    hush_middleware_configure, hush_middleware_add_timeout,
+    hush_middleware_add_session, hush_session_get_value, hush_session_set_value
 };
-pub use builtin::{CorsMiddleware, LoggerMiddleware, AuthMiddleware, RateLimitMiddleware};
\ No newline at end of file
+pub use builtin::{CorsMiddleware, CorsConfig, LoggerMiddleware, AuthMiddleware, RateLimitMiddleware, CsrfMiddleware, TimeoutMiddleware};
+pub use session::{
+    SessionStore, InMemorySessionStore, SessionMiddleware,
+    SessionBackend, InMemorySessionBackend, SignedCookieBackend, SessionHandle
+};
+pub use registry::{ParamSpec, ParamType, MiddlewareRegistration};
\ No newline at end of file
@@ -0,0 +1,255 @@
+// ============================================================================
+// 中间件配置注册表 | Middleware Configuration Registry
+// ============================================================================
+//
+// 让可配置中间件以 JSON 形式暴露自己当前的配置和参数 schema，并可以在不重建
+// 整条链的情况下按名字用新的 JSON 热替换配置。仓库里没有引入 serde 之类的
+// 依赖，这里手写了一个只支持扁平对象（字符串/数字/布尔值）的最小 JSON
+// 读写工具，够用即可。
+//
+// Lets configurable middlewares expose their current configuration and a
+// parameter schema as JSON, and have that config hot-swapped by name without
+// rebuilding the whole chain. The repo doesn't pull in a dependency like
+// serde, so this is a minimal hand-rolled JSON reader/writer that only needs
+// to handle flat objects of string/number/bool values.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::core::error::{HushError, HushResult};
+use super::core::Middleware;
+
+/// 配置参数支持的标量类型
+/// Scalar types a declared config parameter can take
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamType {
+    String,
+    Number,
+    Bool,
+}
+
+impl ParamType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ParamType::String => "string",
+            ParamType::Number => "number",
+            ParamType::Bool => "bool",
+        }
+    }
+}
+
+/// 中间件声明的一个配置参数：名称、类型、是否必填
+/// A single config parameter a middleware declares: name, type, required
+#[derive(Debug, Clone)]
+pub struct ParamSpec {
+    pub name: String,
+    pub param_type: ParamType,
+    pub required: bool,
+}
+
+impl ParamSpec {
+    pub fn new(name: impl Into<String>, param_type: ParamType, required: bool) -> Self {
+        Self { name: name.into(), param_type, required }
+    }
+
+    fn to_json(&self) -> String {
+        format!(
+            r#"{{"name":{},"type":{},"required":{}}}"#,
+            json_quote(&self.name),
+            json_quote(self.param_type.as_str()),
+            self.required
+        )
+    }
+}
+
+/// 根据新的 JSON 配置重新构造一个中间件实例
+/// Rebuilds a fresh middleware instance from a new JSON config
+pub type ConfigFactory = Arc<dyn Fn(&str) -> HushResult<Arc<dyn Middleware>> + Send + Sync>;
+
+/// 中间件在注册表中的元数据：当前生效的配置、声明的参数 schema，以及（若支持
+/// 热重载）用于从新 JSON 重建实例的工厂函数
+/// A middleware's registry metadata: its active config, declared param
+/// schema, and (if it supports hot-reload) the factory used to rebuild it
+/// from new JSON
+#[derive(Clone)]
+pub struct MiddlewareRegistration {
+    pub config_json: String,
+    pub params: Vec<ParamSpec>,
+    pub factory: Option<ConfigFactory>,
+}
+
+impl MiddlewareRegistration {
+    /// 不支持热配置的中间件（函数式中间件、作用域包装器等）使用的空注册信息
+    /// Empty registration used by middlewares that don't support hot
+    /// configuration (function middlewares, scope wrappers, ...)
+    pub fn unconfigured() -> Self {
+        Self { config_json: "{}".to_string(), params: Vec::new(), factory: None }
+    }
+
+    pub fn new(config_json: String, params: Vec<ParamSpec>, factory: ConfigFactory) -> Self {
+        Self { config_json, params, factory: Some(factory) }
+    }
+
+    pub fn to_json(&self, name: &str) -> String {
+        let params_json: Vec<String> = self.params.iter().map(ParamSpec::to_json).collect();
+        format!(
+            r#"{{"name":{},"config":{},"params":[{}]}}"#,
+            json_quote(name),
+            self.config_json,
+            params_json.join(",")
+        )
+    }
+}
+
+/// 为字符串值加上引号并转义 JSON 特殊字符
+/// Quote a string value and escape JSON special characters
+pub fn json_quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// 一个扁平 JSON 对象里的标量值
+/// A scalar value found in a flat JSON object
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonScalar {
+    Str(String),
+    Num(f64),
+    Bool(bool),
+    Null,
+}
+
+impl JsonScalar {
+    pub fn as_str(&self) -> Option<&str> {
+        match self { JsonScalar::Str(s) => Some(s), _ => None }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self { JsonScalar::Num(n) => Some(*n), _ => None }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self { JsonScalar::Bool(b) => Some(*b), _ => None }
+    }
+}
+
+/// 解析一个只含标量值的扁平 JSON 对象，例如 `{"a": "x", "b": 1, "c": true}`。
+/// 不支持嵌套对象/数组，足以覆盖本仓库中间件的配置形状。
+/// Parses a flat JSON object of scalar values only, e.g.
+/// `{"a": "x", "b": 1, "c": true}`. Nested objects/arrays aren't supported —
+/// sufficient for the shape of this repo's middleware configs.
+pub fn parse_flat_json_object(json: &str) -> HushResult<HashMap<String, JsonScalar>> {
+    let trimmed = json.trim();
+    let inner = trimmed
+        .strip_prefix('{')
+        .and_then(|s| s.strip_suffix('}'))
+        .ok_or_else(|| HushError::InvalidInput("Expected a JSON object".to_string()))?;
+
+    let mut result = HashMap::new();
+    for pair in split_top_level(inner, ',') {
+        let pair = pair.trim();
+        if pair.is_empty() {
+            continue;
+        }
+        let mut parts = split_top_level(pair, ':').into_iter();
+        let key_raw = parts.next().ok_or_else(|| HushError::InvalidInput("Malformed JSON entry".to_string()))?;
+        let value_raw = parts
+            .collect::<Vec<_>>()
+            .join(":");
+        if value_raw.is_empty() {
+            return Err(HushError::InvalidInput("Malformed JSON entry: missing value".to_string()));
+        }
+
+        let key = parse_json_string(key_raw.trim())?;
+        let value = parse_json_scalar(value_raw.trim())?;
+        result.insert(key, value);
+    }
+    Ok(result)
+}
+
+fn parse_json_scalar(raw: &str) -> HushResult<JsonScalar> {
+    if raw == "null" {
+        Ok(JsonScalar::Null)
+    } else if raw == "true" {
+        Ok(JsonScalar::Bool(true))
+    } else if raw == "false" {
+        Ok(JsonScalar::Bool(false))
+    } else if raw.starts_with('"') {
+        Ok(JsonScalar::Str(parse_json_string(raw)?))
+    } else {
+        raw.parse::<f64>()
+            .map(JsonScalar::Num)
+            .map_err(|_| HushError::InvalidInput(format!("Invalid JSON value: {}", raw)))
+    }
+}
+
+fn parse_json_string(raw: &str) -> HushResult<String> {
+    let inner = raw
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .ok_or_else(|| HushError::InvalidInput(format!("Expected a JSON string: {}", raw)))?;
+
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('"') => out.push('"'),
+                Some('\\') => out.push('\\'),
+                Some('n') => out.push('\n'),
+                Some('r') => out.push('\r'),
+                Some('t') => out.push('\t'),
+                Some(other) => out.push(other),
+                None => return Err(HushError::InvalidInput("Unterminated escape in JSON string".to_string())),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    Ok(out)
+}
+
+/// 按给定分隔符拆分字符串，但会跳过引号内部的分隔符
+/// Splits a string on a delimiter, skipping delimiters inside quoted strings
+fn split_top_level(s: &str, delim: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for c in s.chars() {
+        if escaped {
+            current.push(c);
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if in_string => {
+                current.push(c);
+                escaped = true;
+            }
+            '"' => {
+                in_string = !in_string;
+                current.push(c);
+            }
+            c if c == delim && !in_string => {
+                parts.push(current.clone());
+                current.clear();
+            }
+            c => current.push(c),
+        }
+    }
+    parts.push(current);
+    parts
+}
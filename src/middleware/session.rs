@@ -0,0 +1,562 @@
+// ============================================================================
+// 会话中间件 | Session Middleware
+// ============================================================================
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::core::crypto::{base64url_decode, base64url_encode, constant_time_eq, hmac_sha256};
+use crate::core::error::HushResult;
+use crate::core::types::RequestContext;
+use super::core::{Middleware, MiddlewareContext, MiddlewareResult, NextFunction};
+
+/// 可插拔的会话存储后端：以会话 id 为键读写数据，默认提供进程内实现，
+/// 也可以换成 Redis 等外部存储
+/// Pluggable backend for session data storage: reads and writes data keyed
+/// by session id. An in-process default is provided here; this can be
+/// swapped for an external store such as Redis
+pub trait SessionStore: Send + Sync {
+    fn get(&self, id: &str) -> Option<HashMap<String, String>>;
+    fn set(&self, id: &str, data: HashMap<String, String>);
+    fn destroy(&self, id: &str);
+}
+
+/// 进程内的默认会话存储，数据不会跨进程/重启持久化
+#[derive(Default)]
+pub struct InMemorySessionStore {
+    sessions: Mutex<HashMap<String, HashMap<String, String>>>,
+}
+
+impl InMemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SessionStore for InMemorySessionStore {
+    fn get(&self, id: &str) -> Option<HashMap<String, String>> {
+        self.sessions.lock().ok()?.get(id).cloned()
+    }
+
+    fn set(&self, id: &str, data: HashMap<String, String>) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.insert(id.to_string(), data);
+        }
+    }
+
+    fn destroy(&self, id: &str) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.remove(id);
+        }
+    }
+}
+
+const DEFAULT_COOKIE_NAME: &str = "session_id";
+/// 会话字段在 `RequestContext.user_data` 中使用的键前缀，FFI 层的
+/// `hush_session_get_value`/`hush_session_set_value` 也复用这个前缀
+/// Key prefix used for session fields in `RequestContext.user_data`; the FFI
+/// layer's `hush_session_get_value`/`hush_session_set_value` reuse the same prefix
+pub(crate) const SESSION_DATA_PREFIX: &str = "session.";
+
+/// 会话数据的编解码后端：决定 cookie 值里到底存了什么，以及怎样从它还原出
+/// 会话数据。签名 cookie 后端把数据整体编码进 cookie 值本身（无状态）；
+/// 进程内存储后端只把一个 id 放进 cookie，真正的数据留在 `SessionStore` 里
+/// Encodes/decodes session data for the cookie value. A signed-cookie
+/// backend packs the whole data map into the cookie value itself
+/// (stateless); the in-memory-store backend puts only an id in the cookie
+/// and keeps the actual data in a `SessionStore`
+pub trait SessionBackend: Send + Sync {
+    /// 从 cookie 值还原会话数据；签名或格式不合法时返回 `None`
+    /// Recovers session data from a cookie value; returns `None` if the
+    /// signature or format is invalid
+    fn decode(&self, cookie_value: &str) -> Option<HashMap<String, String>>;
+
+    /// 把会话数据编码为新的 cookie 值。`existing_cookie_value` 是请求携带的
+    /// 旧值（若有）：内存后端据此决定复用还是新分配 id，签名 cookie 后端
+    /// 忽略它，每次都直接从数据重新计算
+    /// Encodes session data into a new cookie value. `existing_cookie_value`
+    /// is the value the request carried in (if any): the in-memory backend
+    /// uses it to decide whether to reuse or mint a new id, the signed-cookie
+    /// backend ignores it and always recomputes straight from the data
+    fn encode(&self, existing_cookie_value: Option<&str>, data: &HashMap<String, String>) -> String;
+}
+
+fn generate_session_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let counter = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{:x}-{:x}", nanos, counter)
+}
+
+/// 把会话数据留在服务端 `SessionStore` 里的默认后端，cookie 值只是查找用的 id
+/// Default backend that keeps session data on the server in a
+/// `SessionStore`; the cookie value is only a lookup id
+pub struct InMemorySessionBackend {
+    store: Arc<dyn SessionStore>,
+}
+
+impl InMemorySessionBackend {
+    pub fn new(store: Arc<dyn SessionStore>) -> Self {
+        Self { store }
+    }
+}
+
+impl SessionBackend for InMemorySessionBackend {
+    fn decode(&self, cookie_value: &str) -> Option<HashMap<String, String>> {
+        Some(self.store.get(cookie_value).unwrap_or_default())
+    }
+
+    fn encode(&self, existing_cookie_value: Option<&str>, data: &HashMap<String, String>) -> String {
+        let id = existing_cookie_value
+            .map(|value| value.to_string())
+            .unwrap_or_else(generate_session_id);
+        self.store.set(&id, data.clone());
+        id
+    }
+}
+
+/// 极简的签名 cookie 后端：把会话数据序列化后，和用配置密钥算出的
+/// HMAC-SHA256 签名一起放进 cookie 值；下次请求重新计算签名来检测是否被
+/// 篡改，不需要服务端存储。和 `AuthMiddleware::validate_token` 校验 JWT
+/// 签名用的是同一套 `crypto::hmac_sha256` + `constant_time_eq`
+/// A minimal signed-cookie backend: serializes the session data and packs
+/// it into the cookie value alongside an HMAC-SHA256 signature computed
+/// from the configured secret, then recomputes that signature on the next
+/// request to detect tampering — no server-side storage needed. Uses the
+/// same `crypto::hmac_sha256` + `constant_time_eq` pair that
+/// `AuthMiddleware::validate_token` uses to verify JWT signatures
+pub struct SignedCookieBackend {
+    secret: String,
+}
+
+impl SignedCookieBackend {
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self { secret: secret.into() }
+    }
+}
+
+impl SessionBackend for SignedCookieBackend {
+    fn decode(&self, cookie_value: &str) -> Option<HashMap<String, String>> {
+        let (payload, signature) = cookie_value.rsplit_once('.')?;
+        let provided_signature = base64url_decode(signature)?;
+        let expected_signature = base64url_decode(&sign(&self.secret, payload))?;
+        if !constant_time_eq(&expected_signature, &provided_signature) {
+            return None;
+        }
+        Some(decode_payload(payload))
+    }
+
+    fn encode(&self, _existing_cookie_value: Option<&str>, data: &HashMap<String, String>) -> String {
+        let payload = encode_payload(data);
+        let signature = sign(&self.secret, &payload);
+        format!("{}.{}", payload, signature)
+    }
+}
+
+/// 对 payload 计算 HMAC-SHA256 签名，以 base64url 编码返回
+/// Computes the HMAC-SHA256 signature over the payload, base64url-encoded
+fn sign(secret: &str, payload: &str) -> String {
+    base64url_encode(&hmac_sha256(secret.as_bytes(), payload.as_bytes()))
+}
+
+fn encode_payload(data: &HashMap<String, String>) -> String {
+    let mut pairs: Vec<String> = data
+        .iter()
+        .map(|(key, value)| format!("{}={}", cookie_escape(key), cookie_escape(value)))
+        .collect();
+    pairs.sort();
+    pairs.join("&")
+}
+
+fn decode_payload(payload: &str) -> HashMap<String, String> {
+    if payload.is_empty() {
+        return HashMap::new();
+    }
+    payload
+        .split('&')
+        .filter_map(|pair| {
+            let mut parts = pair.splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) => Some((cookie_unescape(key), cookie_unescape(value))),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// 转义除 `[A-Za-z0-9\-_~]` 以外的所有字节，确保编码结果里不会出现 `=`、
+/// `&`、`.`、`;` 这些在 cookie 值/载荷里另有分隔符含义的字符
+/// Escapes every byte outside `[A-Za-z0-9\-_~]`, so the encoded result never
+/// contains `=`, `&`, `.`, `;` or anything else that's meaningful as a
+/// cookie value / payload delimiter
+fn cookie_escape(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for byte in input.as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'~' => out.push(*byte as char),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+fn cookie_unescape(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let (Some(hi), Some(lo)) = (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                output.push(hi * 16 + lo);
+                i += 3;
+                continue;
+            }
+        }
+        output.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// 会话中间件：请求前阶段从 Cookie 中读取会话的 cookie 值，交给 `backend`
+/// 解码出会话数据并写入 `RequestContext.user_data`（`session.` 前缀），供
+/// 处理器和后续中间件通过 `get_user_data`/`set_user_data` 读写；响应阶段把
+/// 当前值交给 `backend` 重新编码，会话是新建的或数据发生了变化时通过
+/// `Set-Cookie` 响应头下发新的 cookie 值
+/// Session middleware: in the pre-phase it reads the session cookie value
+/// from the request and asks `backend` to decode it into session data,
+/// placing that into `RequestContext.user_data` (`session.`-prefixed keys)
+/// so handlers and downstream middleware can read/write it via
+/// `get_user_data`/`set_user_data`; in the response phase it asks `backend`
+/// to re-encode the current values, and sends the new cookie value via
+/// `Set-Cookie` whenever the session was newly created or its data changed
+pub struct SessionMiddleware {
+    backend: Arc<dyn SessionBackend>,
+    cookie_name: String,
+    max_age_secs: Option<u64>,
+}
+
+impl SessionMiddleware {
+    /// 使用进程内存储后端：cookie 里只放一个 id，数据留在 `store` 中
+    /// Uses the in-memory-store backend: the cookie only carries an id, the
+    /// data itself stays in `store`
+    pub fn new(store: Arc<dyn SessionStore>) -> Self {
+        Self::with_backend(Arc::new(InMemorySessionBackend::new(store)))
+    }
+
+    /// 使用签名 cookie 后端：会话数据整体编码进 cookie 值，用 `secret` 签名
+    /// 以检测篡改，不需要任何服务端存储
+    /// Uses the signed-cookie backend: the whole session data is encoded
+    /// into the cookie value and signed with `secret` to detect tampering,
+    /// with no server-side storage needed
+    pub fn with_signed_cookie(secret: impl Into<String>) -> Self {
+        Self::with_backend(Arc::new(SignedCookieBackend::new(secret)))
+    }
+
+    pub fn with_backend(backend: Arc<dyn SessionBackend>) -> Self {
+        Self {
+            backend,
+            cookie_name: DEFAULT_COOKIE_NAME.to_string(),
+            max_age_secs: None,
+        }
+    }
+
+    pub fn with_cookie_name(mut self, cookie_name: &str) -> Self {
+        self.cookie_name = cookie_name.to_string();
+        self
+    }
+
+    /// 设置 `Set-Cookie` 的 `Max-Age` 属性；不调用则不下发该属性（会话 cookie）
+    /// Sets the `Max-Age` attribute on `Set-Cookie`; if not called, no such
+    /// attribute is sent (a session-lifetime cookie)
+    pub fn with_max_age(mut self, max_age_secs: u64) -> Self {
+        self.max_age_secs = Some(max_age_secs);
+        self
+    }
+
+    fn cookie_value_from_request(&self, request: &RequestContext) -> Option<String> {
+        let cookie_header = request.get_header("Cookie")?;
+        cookie_header.split(';').find_map(|pair| {
+            let mut parts = pair.trim().splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some(name), Some(value)) if name == self.cookie_name => Some(value.to_string()),
+                _ => None,
+            }
+        })
+    }
+}
+
+impl Middleware for SessionMiddleware {
+    fn process(&self, context: &mut MiddlewareContext, next: NextFunction) -> HushResult<MiddlewareResult> {
+        let existing_cookie_value = self.cookie_value_from_request(&context.request);
+        let is_new = existing_cookie_value.is_none();
+        let data = existing_cookie_value
+            .as_deref()
+            .and_then(|value| self.backend.decode(value))
+            .unwrap_or_default();
+
+        for (key, value) in &data {
+            context.request.set_user_data(format!("{}{}", SESSION_DATA_PREFIX, key), value.clone());
+        }
+
+        let result = next(context)?;
+
+        let session_data: HashMap<String, String> = context.request.user_data
+            .iter()
+            .filter_map(|(key, value)| {
+                key.strip_prefix(SESSION_DATA_PREFIX).map(|field| (field.to_string(), value.clone()))
+            })
+            .collect();
+        let changed = session_data != data;
+        let new_cookie_value = self.backend.encode(existing_cookie_value.as_deref(), &session_data);
+
+        match result {
+            MiddlewareResult::Response(mut response) => {
+                if is_new || changed {
+                    let mut cookie = format!("{}={}; Path=/; HttpOnly", self.cookie_name, new_cookie_value);
+                    if let Some(max_age) = self.max_age_secs {
+                        cookie.push_str(&format!("; Max-Age={}", max_age));
+                    }
+                    response.add_header("Set-Cookie".to_string(), cookie);
+                }
+                Ok(MiddlewareResult::Response(response))
+            }
+            other => Ok(other),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "session"
+    }
+
+    fn priority(&self) -> i32 {
+        12 // 在 CORS(10) 之后、限流/认证(15/20) 之前，使会话数据对它们可用
+    }
+}
+
+/// 对 `RequestContext.user_data` 中会话字段的类型化、可变视图：调用方不需要
+/// 手动拼接 [`SESSION_DATA_PREFIX`]。读写的就是 `SessionMiddleware` 预先写入
+/// 的同一份存储，响应阶段会被 `backend` 重新编码进 cookie
+/// A typed, mutable view over the session fields in
+/// `RequestContext.user_data`, so callers don't have to prepend
+/// [`SESSION_DATA_PREFIX`] by hand. Reads/writes the same storage
+/// `SessionMiddleware` pre-populates, which gets re-encoded into the cookie
+/// by the `backend` on the way out
+pub struct SessionHandle<'a> {
+    request: &'a mut RequestContext,
+}
+
+impl<'a> SessionHandle<'a> {
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.request.get_user_data(&format!("{}{}", SESSION_DATA_PREFIX, key))
+    }
+
+    pub fn set(&mut self, key: &str, value: impl Into<String>) {
+        self.request.set_user_data(format!("{}{}", SESSION_DATA_PREFIX, key), value.into());
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.request.remove_user_data(&format!("{}{}", SESSION_DATA_PREFIX, key))
+    }
+}
+
+impl MiddlewareContext {
+    /// 获取当前请求会话数据的类型化、可变视图，参见 [`SessionHandle`]
+    /// Returns a typed, mutable view over the current request's session
+    /// data, see [`SessionHandle`]
+    pub fn session(&mut self) -> SessionHandle<'_> {
+        SessionHandle { request: &mut self.request }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::{HttpMethod, HttpStatus, ResponseContext};
+
+    fn next_ok() -> NextFunction {
+        Box::new(|_ctx| Ok(MiddlewareResult::Response(ResponseContext::with_text(HttpStatus::Ok, "ok"))))
+    }
+
+    #[test]
+    fn test_in_memory_session_store_roundtrip() {
+        let store = InMemorySessionStore::new();
+        assert!(store.get("abc").is_none());
+
+        let mut data = HashMap::new();
+        data.insert("user".to_string(), "ada".to_string());
+        store.set("abc", data.clone());
+        assert_eq!(store.get("abc"), Some(data));
+
+        store.destroy("abc");
+        assert!(store.get("abc").is_none());
+    }
+
+    #[test]
+    fn test_session_middleware_issues_new_session_cookie() {
+        let store = Arc::new(InMemorySessionStore::new());
+        let middleware = SessionMiddleware::new(store);
+
+        let request = RequestContext::new(HttpMethod::GET, "/".to_string());
+        let mut context = MiddlewareContext::new(request);
+
+        let result = middleware.process(&mut context, next_ok()).unwrap();
+        match result {
+            MiddlewareResult::Response(response) => {
+                assert!(response.headers.get("Set-Cookie").unwrap().starts_with("session_id="));
+            }
+            _ => panic!("expected a response"),
+        }
+    }
+
+    #[test]
+    fn test_session_middleware_loads_existing_session_into_user_data() {
+        let store = Arc::new(InMemorySessionStore::new());
+        let mut existing = HashMap::new();
+        existing.insert("username".to_string(), "grace".to_string());
+        store.set("session-42", existing);
+        let middleware = SessionMiddleware::new(store);
+
+        let mut request = RequestContext::new(HttpMethod::GET, "/".to_string());
+        request.add_header("Cookie".to_string(), "session_id=session-42".to_string());
+        let mut context = MiddlewareContext::new(request);
+
+        let next: NextFunction = Box::new(|ctx| {
+            assert_eq!(ctx.request.get_user_data("session.username"), Some(&"grace".to_string()));
+            Ok(MiddlewareResult::Response(ResponseContext::with_text(HttpStatus::Ok, "ok")))
+        });
+
+        let result = middleware.process(&mut context, next).unwrap();
+        match result {
+            MiddlewareResult::Response(response) => {
+                assert!(response.headers.get("Set-Cookie").is_none());
+            }
+            _ => panic!("expected a response"),
+        }
+    }
+
+    #[test]
+    fn test_session_middleware_persists_mutations_made_downstream() {
+        let store = Arc::new(InMemorySessionStore::new());
+        let middleware = SessionMiddleware::new(Arc::clone(&store) as Arc<dyn SessionStore>);
+
+        let mut request = RequestContext::new(HttpMethod::GET, "/".to_string());
+        request.add_header("Cookie".to_string(), "session_id=session-7".to_string());
+        let mut context = MiddlewareContext::new(request);
+
+        let next: NextFunction = Box::new(|ctx| {
+            ctx.request.set_user_data("session.cart_count".to_string(), "3".to_string());
+            Ok(MiddlewareResult::Response(ResponseContext::with_text(HttpStatus::Ok, "ok")))
+        });
+
+        middleware.process(&mut context, next).unwrap();
+
+        let mut expected = HashMap::new();
+        expected.insert("cart_count".to_string(), "3".to_string());
+        assert_eq!(store.get("session-7"), Some(expected));
+    }
+
+    #[test]
+    fn test_signed_cookie_backend_round_trips_data() {
+        let backend = SignedCookieBackend::new("s3cr3t");
+        let mut data = HashMap::new();
+        data.insert("user".to_string(), "ada".to_string());
+
+        let cookie_value = backend.encode(None, &data);
+        assert_eq!(backend.decode(&cookie_value), Some(data));
+    }
+
+    #[test]
+    fn test_signed_cookie_backend_rejects_tampered_payload() {
+        let backend = SignedCookieBackend::new("s3cr3t");
+        let mut data = HashMap::new();
+        data.insert("role".to_string(), "admin".to_string());
+
+        let mut cookie_value = backend.encode(None, &data);
+        cookie_value.push('x');
+        assert!(backend.decode(&cookie_value).is_none());
+    }
+
+    #[test]
+    fn test_signed_cookie_backend_rejects_wrong_secret() {
+        let mut data = HashMap::new();
+        data.insert("role".to_string(), "admin".to_string());
+        let cookie_value = SignedCookieBackend::new("secret-a").encode(None, &data);
+
+        assert!(SignedCookieBackend::new("secret-b").decode(&cookie_value).is_none());
+    }
+
+    #[test]
+    fn test_session_handle_reads_and_writes_through_the_prefix() {
+        let store = Arc::new(InMemorySessionStore::new());
+        let mut existing = HashMap::new();
+        existing.insert("username".to_string(), "grace".to_string());
+        store.set("session-99", existing);
+        let middleware = SessionMiddleware::new(Arc::clone(&store) as Arc<dyn SessionStore>);
+
+        let mut request = RequestContext::new(HttpMethod::GET, "/".to_string());
+        request.add_header("Cookie".to_string(), "session_id=session-99".to_string());
+        let mut context = MiddlewareContext::new(request);
+
+        let next: NextFunction = Box::new(|ctx| {
+            assert_eq!(ctx.session().get("username"), Some(&"grace".to_string()));
+            ctx.session().set("cart_count", "1");
+            assert_eq!(ctx.session().remove("username"), Some("grace".to_string()));
+            assert!(ctx.session().get("username").is_none());
+            Ok(MiddlewareResult::Response(ResponseContext::with_text(HttpStatus::Ok, "ok")))
+        });
+
+        middleware.process(&mut context, next).unwrap();
+
+        let mut expected = HashMap::new();
+        expected.insert("cart_count".to_string(), "1".to_string());
+        assert_eq!(store.get("session-99"), Some(expected));
+    }
+
+    #[test]
+    fn test_session_middleware_with_signed_cookie_round_trips_mutations() {
+        let middleware = SessionMiddleware::with_signed_cookie("s3cr3t");
+
+        let request = RequestContext::new(HttpMethod::GET, "/".to_string());
+        let mut context = MiddlewareContext::new(request);
+        let first_next: NextFunction = Box::new(|ctx| {
+            ctx.request.set_user_data("session.user".to_string(), "ada".to_string());
+            Ok(MiddlewareResult::Response(ResponseContext::with_text(HttpStatus::Ok, "ok")))
+        });
+        let first_result = middleware.process(&mut context, first_next).unwrap();
+        let set_cookie = match first_result {
+            MiddlewareResult::Response(response) => response.headers.get("Set-Cookie").unwrap().clone(),
+            _ => panic!("expected a response"),
+        };
+        let cookie_value = set_cookie
+            .split(';')
+            .next()
+            .unwrap()
+            .trim_start_matches("session_id=")
+            .to_string();
+
+        let mut request = RequestContext::new(HttpMethod::GET, "/".to_string());
+        request.add_header("Cookie".to_string(), format!("session_id={}", cookie_value));
+        let mut context = MiddlewareContext::new(request);
+        let second_next: NextFunction = Box::new(|ctx| {
+            assert_eq!(ctx.request.get_user_data("session.user"), Some(&"ada".to_string()));
+            Ok(MiddlewareResult::Response(ResponseContext::with_text(HttpStatus::Ok, "ok")))
+        });
+        middleware.process(&mut context, second_next).unwrap();
+    }
+}
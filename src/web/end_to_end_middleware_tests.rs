@@ -6,6 +6,7 @@
 mod tests {
     use super::super::server::{WebServer, WebServerConfig};
     use super::super::handler::RequestHandler;
+    use super::super::test_client::TestClient;
     use crate::core::types::{HttpMethod, ResponseContext, HttpStatus};
     use crate::middleware::core::{MiddlewareResult};
     use std::time::Duration;
@@ -19,6 +20,8 @@ mod tests {
             max_connections: 100,
             keep_alive: 10,
             request_timeout: 10,
+            shutdown_timeout: 10,
+            template_dir: "templates".to_string(),
         };
         WebServer::new(config)
     }
@@ -30,13 +33,6 @@ mod tests {
         })
     }
 
-    /// 发送 HTTP 请求的辅助函数（简化版，实际应该使用 HTTP 客户端）
-    fn make_http_request(url: &str) -> Result<String, Box<dyn std::error::Error>> {
-        // 这里我们只是模拟 HTTP 请求，实际测试中应该使用真实的 HTTP 客户端
-        // 由于这是一个集成测试，我们主要验证服务器能够正常启动和配置
-        Ok(format!("Mock response for {}", url))
-    }
-
     #[test]
     fn test_end_to_end_middleware_chain() {
         let port = 18081;
@@ -46,7 +42,7 @@ mod tests {
         server.add_logger_middleware().unwrap();
         
         // 添加 CORS 中间件
-        server.add_cors_middleware("*".to_string()).unwrap();
+        server.add_cors_middleware("*".to_string(), false).unwrap();
         
         // 添加自定义中间件来记录请求
         server.add_middleware("request_counter".to_string(), |ctx, next| {
@@ -115,6 +111,37 @@ mod tests {
         server.stop().unwrap();
     }
 
+    #[test]
+    fn test_custom_error_handler_registers_without_affecting_chain_execution() {
+        let port = 18092;
+        let server = create_test_server(port);
+
+        // 注册一个自定义的错误转换函数，覆盖内置的默认映射表
+        server.set_error_handler(|error| {
+            ResponseContext::with_json(
+                HttpStatus::BadRequest,
+                &format!(r#"{{"custom_error": "{}"}}"#, error),
+            )
+        }).unwrap();
+
+        server.add_middleware("error_middleware".to_string(), |_ctx, _next| {
+            Ok(MiddlewareResult::Error(
+                crate::core::error::HushError::InternalError("boom".to_string())
+            ))
+        }).unwrap();
+
+        let handler = create_test_handler("This should not be reached");
+        server.add_route(HttpMethod::GET, "/error-test", handler).unwrap();
+
+        let start_result = server.start_with_port(port);
+        assert!(start_result.is_ok(), "Server should start successfully");
+
+        thread::sleep(Duration::from_millis(200));
+        assert!(server.is_running(), "Server should be running");
+
+        server.stop().unwrap();
+    }
+
     #[test]
     fn test_middleware_early_response_end_to_end() {
         let port = 18083;
@@ -128,21 +155,30 @@ mod tests {
             );
             Ok(MiddlewareResult::Response(response))
         }).unwrap();
-        
+
         // 添加测试路由（这个不应该被执行）
         let handler = create_test_handler("This should not be reached");
         server.add_route(HttpMethod::GET, "/early-test", handler).unwrap();
-        
+
         // 启动服务器
         let start_result = server.start_with_port(port);
         assert!(start_result.is_ok(), "Server should start successfully");
-        
+
         // 等待服务器启动
         thread::sleep(Duration::from_millis(200));
-        
+
         // 验证服务器正在运行
         assert!(server.is_running(), "Server should be running");
-        
+
+        // 验证中间件确实提前返回了自己的 JSON 响应，处理器完全没有被执行
+        let client = TestClient::new(port);
+        let response = client.get("/early-test").expect("request should succeed");
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            response.body_as_string().unwrap(),
+            r#"{"message": "Early response from middleware", "source": "middleware"}"#
+        );
+
         // 停止服务器
         server.stop().unwrap();
     }
@@ -153,7 +189,7 @@ mod tests {
         let server = create_test_server(port);
         
         // 添加 CORS 中间件
-        server.add_cors_middleware("https://example.com".to_string()).unwrap();
+        server.add_cors_middleware("https://example.com".to_string(), false).unwrap();
         
         // 添加测试路由
         let handler = create_test_handler("CORS test response");
@@ -172,7 +208,15 @@ mod tests {
         
         // 验证服务器正在运行
         assert!(server.is_running(), "Server should be running");
-        
+
+        // 真正发起一次请求，验证 CORS 响应头确实出现在响应里
+        let client = TestClient::new(port);
+        let response = client
+            .request_with_headers(HttpMethod::GET, "/cors", &[("Origin", "https://example.com")], None)
+            .expect("request should succeed");
+        assert_eq!(response.status, 200);
+        assert_eq!(response.header("Access-Control-Allow-Origin"), Some("https://example.com"));
+
         // 停止服务器
         server.stop().unwrap();
     }
@@ -202,7 +246,17 @@ mod tests {
         
         // 验证服务器正在运行
         assert!(server.is_running(), "Server should be running");
-        
+
+        let client = TestClient::new(port);
+
+        // 未携带令牌访问受保护的路由应当被拒绝
+        let rejected = client.get("/protected").expect("request should succeed");
+        assert_eq!(rejected.status, 401);
+
+        // 健康检查路径在跳过列表里，不需要认证
+        let health = client.get("/health").expect("request should succeed");
+        assert_eq!(health.status, 200);
+
         // 停止服务器
         server.stop().unwrap();
     }
@@ -218,7 +272,7 @@ mod tests {
         server.add_logger_middleware().unwrap();
         
         // 2. CORS 中间件
-        server.add_cors_middleware("*".to_string()).unwrap();
+        server.add_cors_middleware("*".to_string(), false).unwrap();
         
         // 3. 请求 ID 中间件
         server.add_middleware("request_id".to_string(), |ctx, next| {
@@ -276,7 +330,14 @@ mod tests {
         // 验证中间件数量
         println!("Total middleware count: {}", server.middleware_count());
         assert!(server.middleware_count() >= 5, "Should have at least 5 middleware");
-        
+
+        // 验证响应头中间件确实给响应加上了 X-Powered-By 和 X-Request-ID
+        let client = TestClient::new(port);
+        let response = client.get("/complex").expect("request should succeed");
+        assert_eq!(response.status, 200);
+        assert_eq!(response.header("X-Powered-By"), Some("Hush Framework"));
+        assert!(response.header("X-Request-ID").is_some(), "Response should carry an X-Request-ID header");
+
         // 停止服务器
         server.stop().unwrap();
     }
@@ -0,0 +1,72 @@
+// ============================================================================
+// 错误处理器注册表 | Error Handler Registry
+// ============================================================================
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::core::types::{HttpStatus, ResponseContext};
+
+/// 按状态码注册的错误处理闭包
+pub type ErrorHandlerFn = Arc<dyn Fn(ResponseContext) -> ResponseContext + Send + Sync>;
+
+/// 按 HTTP 状态码注册的自定义错误处理器集合：当链执行或路由处理产生某个
+/// 状态码的响应（包括映射到该状态码的错误，如 404/405/500/408）时，对应的
+/// 处理器有机会在响应写回前替换其响应体/响应头（例如自定义错误页、统一
+/// 的 JSON 错误信封）。
+/// A collection of custom error handlers keyed by HTTP status code: when the
+/// chain or a route handler produces a response for that status (including
+/// errors mapped to one, e.g. 404/405/500/408), the matching handler gets a
+/// chance to replace the body/headers before it's written back (e.g. a
+/// custom error page, a uniform JSON error envelope).
+#[derive(Clone, Default)]
+pub struct ErrorHandlers {
+    handlers: HashMap<u16, ErrorHandlerFn>,
+}
+
+impl ErrorHandlers {
+    pub fn new() -> Self {
+        Self {
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// 为指定状态码注册处理器，同一状态码再次注册会覆盖之前的处理器
+    pub fn register<F>(&mut self, status: HttpStatus, handler: F)
+    where
+        F: Fn(ResponseContext) -> ResponseContext + Send + Sync + 'static,
+    {
+        self.handlers.insert(status.as_u16(), Arc::new(handler));
+    }
+
+    /// 若响应状态码注册了处理器，用其转换结果替换原响应；否则原样返回
+    pub fn apply(&self, response: ResponseContext) -> ResponseContext {
+        match self.handlers.get(&response.status.as_u16()) {
+            Some(handler) => handler(response),
+            None => response,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::HttpStatus;
+
+    #[test]
+    fn test_error_handlers_transforms_matching_status() {
+        let mut handlers = ErrorHandlers::new();
+        handlers.register(HttpStatus::NotFound, |_response| {
+            ResponseContext::with_json(HttpStatus::NotFound, r#"{"error": "not found"}"#)
+        });
+
+        let response = handlers.apply(ResponseContext::with_text(HttpStatus::NotFound, "Route not found"));
+        assert_eq!(response.body_as_string().unwrap(), r#"{"error": "not found"}"#);
+    }
+
+    #[test]
+    fn test_error_handlers_passes_through_unregistered_status() {
+        let handlers = ErrorHandlers::new();
+        let response = handlers.apply(ResponseContext::with_text(HttpStatus::Ok, "hello"));
+        assert_eq!(response.body_as_string().unwrap(), "hello");
+    }
+}
@@ -20,6 +20,8 @@ pub fn demo_middleware_integration() {
         max_connections: 1000,
         keep_alive: 30,
         request_timeout: 30,
+        shutdown_timeout: 30,
+        template_dir: "templates".to_string(),
     };
     let server = WebServer::new(config);
     
@@ -27,7 +29,7 @@ pub fn demo_middleware_integration() {
     server.add_logger_middleware().unwrap();
     
     println!("2. 添加 CORS 中间件...");
-    server.add_cors_middleware("*".to_string()).unwrap();
+    server.add_cors_middleware("*".to_string(), false).unwrap();
     
     println!("3. 添加自定义请求计数中间件...");
     server.add_middleware("request_counter".to_string(), |ctx, next| {
@@ -241,7 +243,7 @@ mod tests {
         
         // 添加中间件
         server.add_logger_middleware().unwrap();
-        server.add_cors_middleware("*".to_string()).unwrap();
+        server.add_cors_middleware("*".to_string(), false).unwrap();
         
         // 添加路由
         let handler = RequestHandler::new(|_| {
@@ -20,6 +20,8 @@ mod tests {
             max_connections: 100,
             keep_alive: 10,
             request_timeout: 10,
+            shutdown_timeout: 10,
+            template_dir: "templates".to_string(),
         };
         WebServer::new(config)
     }
@@ -58,7 +60,7 @@ mod tests {
         let server = create_test_server();
         
         // 添加 CORS 中间件
-        server.add_cors_middleware("*".to_string()).unwrap();
+        server.add_cors_middleware("*".to_string(), false).unwrap();
         
         // 添加一个测试路由
         let handler = create_test_handler("CORS test");
@@ -104,7 +106,7 @@ mod tests {
         
         // 添加多个中间件
         server.add_logger_middleware().unwrap();
-        server.add_cors_middleware("https://example.com".to_string()).unwrap();
+        server.add_cors_middleware("https://example.com".to_string(), false).unwrap();
         server.add_auth_middleware("secret_key".to_string()).unwrap();
         
         // 添加自定义中间件
@@ -199,7 +201,7 @@ mod tests {
         
         // 添加中间件
         server.add_logger_middleware().unwrap();
-        server.add_cors_middleware("*".to_string()).unwrap();
+        server.add_cors_middleware("*".to_string(), false).unwrap();
         
         // 添加路由
         let handler = create_test_handler("Startup test");
@@ -5,7 +5,12 @@
 pub mod server;
 pub mod router;
 pub mod handler;
+pub mod scope;
+pub mod testing;
 pub mod middleware_demo;
+pub mod error_handlers;
+pub mod template;
+mod percent_encoding;
 
 #[cfg(test)]
 pub mod middleware_integration_tests;
@@ -13,7 +18,14 @@ pub mod middleware_integration_tests;
 #[cfg(test)]
 pub mod end_to_end_middleware_tests;
 
+#[cfg(test)]
+pub mod test_client;
+
 // 重新导出核心类型和函数
 pub use server::{WebServer, WebServerConfig};
 pub use router::{Router, RouteMatcher};
-pub use handler::{RequestHandler, ResponseBuilder};
\ No newline at end of file
+pub use handler::{RequestHandler, ResponseBuilder};
+pub use scope::Scope;
+pub use testing::{TestRequest, TestServer};
+pub use error_handlers::ErrorHandlers;
+pub use template::TemplateEngine;
\ No newline at end of file
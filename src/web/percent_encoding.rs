@@ -0,0 +1,76 @@
+// ============================================================================
+// 百分号解码 | Percent Decoding
+// ============================================================================
+//
+// 供 [`super::router::RouteMatcher`] 解码捕获的路径段和 [`super::server`]
+// 解码查询参数共用的小工具，避免两处各写一份。
+// Small shared helper used by both [`super::router::RouteMatcher`] to decode
+// captured path segments and [`super::server`] to decode query params, so
+// the logic isn't duplicated in two places.
+
+/// 将一个十六进制数字字符解析为其数值，非法字符返回 `None`
+fn hex_value(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// 解码字符串中的 `%XX` 转义序列；非法或截断的转义序列保留原始字面文本。
+/// Decode `%XX` escape sequences in a string; invalid or truncated escape
+/// sequences are left as literal text rather than causing an error.
+pub(crate) fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let (Some(hi), Some(lo)) = (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                output.push(hi * 16 + lo);
+                i += 3;
+                continue;
+            }
+        }
+        output.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+/// 与 [`percent_decode`] 相同，但先将 `+` 替换为空格，用于
+/// `application/x-www-form-urlencoded` 风格的查询字符串。
+/// Same as [`percent_decode`], but first replaces `+` with a space — for
+/// `application/x-www-form-urlencoded`-style query strings.
+pub(crate) fn percent_decode_plus(input: &str) -> String {
+    percent_decode(&input.replace('+', " "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_decode_reserved_characters() {
+        assert_eq!(percent_decode("john%20doe"), "john doe");
+        assert_eq!(percent_decode("a%2Fb"), "a/b");
+        assert_eq!(percent_decode("100%25"), "100%");
+    }
+
+    #[test]
+    fn test_percent_decode_plus_as_space_in_query() {
+        assert_eq!(percent_decode_plus("hello+world"), "hello world");
+        assert_eq!(percent_decode_plus("a%2Bb"), "a+b");
+    }
+
+    #[test]
+    fn test_percent_decode_invalid_or_truncated_escapes_fall_back_to_literal() {
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("100%2"), "100%2");
+        assert_eq!(percent_decode("100%zz"), "100%zz");
+        assert_eq!(percent_decode("no escapes here"), "no escapes here");
+    }
+}
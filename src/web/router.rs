@@ -6,10 +6,20 @@ use std::collections::HashMap;
 use crate::core::error::{HushError, HushResult};
 use crate::core::types::{HttpMethod, RequestContext, ResponseContext, RouteInfo};
 use super::handler::RequestHandler;
+use super::percent_encoding::percent_decode;
 
 /// 路由管理器
+///
+/// 字面量路径走 `routes` 的精确哈希查找（快速路径），带 `:param` 或 `*wildcard`
+/// 段的路径编译为 `RouteMatcher` 存入 `patterns`，在精确查找未命中时按最高
+/// 优先级（字面量 > 参数 > 通配符）依次尝试。
+/// Literal paths use the `routes` exact hash lookup (fast path); paths with a
+/// `:param` or `*wildcard` segment are compiled into a `RouteMatcher` and stored
+/// in `patterns`, tried in order of specificity (literal > param > wildcard)
+/// when the exact lookup misses.
 pub struct Router {
     routes: HashMap<String, RequestHandler>,
+    patterns: Vec<(HttpMethod, RouteMatcher, RequestHandler)>,
     route_info: Vec<RouteInfo>,
 }
 
@@ -18,79 +28,129 @@ impl Router {
     pub fn new() -> Self {
         Self {
             routes: HashMap::new(),
+            patterns: Vec::new(),
             route_info: Vec::new(),
         }
     }
-    
+
+    /// 路径是否带有动态段（`:param` 或 `*wildcard`）
+    fn is_dynamic(path: &str) -> bool {
+        path.split('/').any(|segment| segment.starts_with(':') || segment.starts_with('*'))
+    }
+
     /// 添加路由
     pub fn add_route(&mut self, method: HttpMethod, path: String, handler: RequestHandler) -> HushResult<()> {
         let route_key = format!("{}:{}", method.as_str(), path);
-        
+
+        if Self::is_dynamic(&path) {
+            if self.patterns.iter().any(|(m, matcher, _)| *m == method && matcher.pattern() == path) {
+                return Err(HushError::HttpError(format!("Route already exists: {}", route_key)));
+            }
+            let route_info = RouteInfo::new(method.clone(), path.clone(), format!("handler_{}", self.route_count()));
+            self.route_info.push(route_info);
+            self.patterns.push((method, RouteMatcher::new(&path), handler));
+            return Ok(());
+        }
+
         // 检查路由是否已存在
         if self.routes.contains_key(&route_key) {
             return Err(HushError::HttpError(format!("Route already exists: {}", route_key)));
         }
-        
+
         // 添加路由信息
-        let route_info = RouteInfo::new(method, path, format!("handler_{}", self.routes.len()));
+        let route_info = RouteInfo::new(method, path, format!("handler_{}", self.route_count()));
         self.route_info.push(route_info);
-        
+
         // 添加路由处理器
         self.routes.insert(route_key, handler);
-        
+
         Ok(())
     }
-    
+
     /// 移除路由
     pub fn remove_route(&mut self, method: HttpMethod, path: &str) -> HushResult<()> {
         let route_key = format!("{}:{}", method.as_str(), path);
-        
-        if self.routes.remove(&route_key).is_none() {
+
+        if Self::is_dynamic(path) {
+            let before = self.patterns.len();
+            self.patterns.retain(|(m, matcher, _)| !(*m == method && matcher.pattern() == path));
+            if self.patterns.len() == before {
+                return Err(HushError::RouteNotFound);
+            }
+        } else if self.routes.remove(&route_key).is_none() {
             return Err(HushError::RouteNotFound);
         }
-        
+
         // 移除路由信息
         self.route_info.retain(|info| info.route_key() != route_key);
-        
+
         Ok(())
     }
-    
-    /// 处理请求
+
+    /// 处理请求：先尝试精确字面量匹配，未命中时回退到按特异性排序的动态模式匹配。
+    /// 若路径能被其他方法匹配，说明路径存在但方法不支持，返回 405 而非 404。
+    /// Handle a request: try the exact literal match first, falling back to
+    /// dynamic pattern matching ordered by specificity when it misses. If the
+    /// path matches under a different method, the path exists but the method
+    /// doesn't — return 405 rather than 404.
     pub fn handle_request(&self, context: &RequestContext) -> HushResult<ResponseContext> {
         let route_key = format!("{}:{}", context.method.as_str(), context.path);
-        
-        match self.routes.get(&route_key) {
-            Some(handler) => handler.handle(context),
-            None => Err(HushError::RouteNotFound),
+
+        if let Some(handler) = self.routes.get(&route_key) {
+            return handler.handle(context);
+        }
+
+        if let Some((handler, params)) = self.match_pattern(&context.method, &context.path) {
+            let mut context = context.clone();
+            context.set_path_params(params);
+            return handler.handle(&context);
+        }
+
+        if !self.get_supported_methods(&context.path).is_empty() {
+            return Err(HushError::MethodNotAllowed);
         }
+
+        Err(HushError::RouteNotFound)
+    }
+
+    /// 在已注册的动态模式中找到最匹配该方法和路径的那一条，优先级为字面量 > 参数 > 通配符
+    /// Find the best-matching dynamic pattern for this method and path, preferring literal > param > wildcard
+    fn match_pattern(&self, method: &HttpMethod, path: &str) -> Option<(&RequestHandler, HashMap<String, String>)> {
+        self.patterns
+            .iter()
+            .filter(|(m, _, _)| m == method)
+            .filter_map(|(_, matcher, handler)| matcher.matches(path).map(|params| (matcher.specificity(), handler, params)))
+            .min_by_key(|(specificity, _, _)| *specificity)
+            .map(|(_, handler, params)| (handler, params))
     }
-    
+
     /// 获取所有路由信息
     pub fn get_routes(&self) -> &Vec<RouteInfo> {
         &self.route_info
     }
-    
-    /// 检查路由是否存在
+
+    /// 检查路由是否存在（同时考虑字面量路由和能匹配该路径的动态模式）
     pub fn has_route(&self, method: HttpMethod, path: &str) -> bool {
         let route_key = format!("{}:{}", method.as_str(), path);
-        self.routes.contains_key(&route_key)
+        self.routes.contains_key(&route_key) || self.match_pattern(&method, path).is_some()
     }
-    
+
     /// 获取路由数量
     pub fn route_count(&self) -> usize {
-        self.routes.len()
+        self.routes.len() + self.patterns.len()
     }
-    
+
     /// 清空所有路由
     pub fn clear(&mut self) {
         self.routes.clear();
+        self.patterns.clear();
         self.route_info.clear();
     }
-    
+
     /// 获取支持的 HTTP 方法列表（针对特定路径）
     pub fn get_supported_methods(&self, path: &str) -> Vec<HttpMethod> {
         let mut methods = Vec::new();
-        
+
         for method in &[
             HttpMethod::GET,
             HttpMethod::POST,
@@ -104,7 +164,7 @@ impl Router {
                 methods.push(method.clone());
             }
         }
-        
+
         methods
     }
 }
@@ -119,7 +179,7 @@ impl Default for Router {
 // 路由匹配器 | Route Matcher
 // ============================================================================
 
-/// 路由匹配器，支持路径参数和通配符
+/// 路由匹配器，支持路径参数（`:name`）和尾部通配符（`*name`，捕获剩余路径）
 pub struct RouteMatcher {
     pattern: String,
     params: Vec<String>,
@@ -129,51 +189,82 @@ impl RouteMatcher {
     /// 创建新的路由匹配器
     pub fn new(pattern: &str) -> Self {
         let mut params = Vec::new();
-        
-        // 解析路径参数（如 /users/:id）
+
+        // 解析路径参数（如 /users/:id）和尾部通配符（如 /static/*tail）
         for segment in pattern.split('/') {
-            if segment.starts_with(':') {
-                params.push(segment[1..].to_string());
+            if let Some(name) = segment.strip_prefix(':') {
+                params.push(name.to_string());
+            } else if let Some(name) = segment.strip_prefix('*') {
+                params.push(name.to_string());
             }
         }
-        
+
         Self {
             pattern: pattern.to_string(),
             params,
         }
     }
-    
-    /// 匹配路径并提取参数
+
+    /// 匹配路径并提取参数。尾部通配符段（`*name`）必须是模式的最后一段，
+    /// 匹配时会把剩余的路径（可能含多个 `/`）整体捕获为该参数的值。
+    /// 捕获值在按 `/` 切分之后逐段百分号解码，因此段内的 `%2F` 会解码为
+    /// 字面量 `/` 而不会被当成额外的路径分隔符。
+    /// Match a path and extract params. A trailing wildcard segment (`*name`)
+    /// must be the pattern's last segment; it captures the rest of the path
+    /// (possibly containing further `/`) as a single param value. Captured
+    /// values are percent-decoded per-segment after splitting on `/`, so a
+    /// `%2F` inside one segment decodes to a literal `/` instead of being
+    /// treated as an extra path separator.
     pub fn matches(&self, path: &str) -> Option<HashMap<String, String>> {
         let pattern_segments: Vec<&str> = self.pattern.split('/').collect();
         let path_segments: Vec<&str> = path.split('/').collect();
-        
-        // 段数必须相同
-        if pattern_segments.len() != path_segments.len() {
-            return None;
-        }
-        
+
         let mut params = HashMap::new();
-        
-        for (pattern_seg, path_seg) in pattern_segments.iter().zip(path_segments.iter()) {
-            if pattern_seg.starts_with(':') {
-                // 路径参数
-                let param_name = &pattern_seg[1..];
-                params.insert(param_name.to_string(), path_seg.to_string());
-            } else if *pattern_seg != *path_seg {
+
+        for (index, pattern_seg) in pattern_segments.iter().enumerate() {
+            if let Some(name) = pattern_seg.strip_prefix('*') {
+                // 通配符段：必须是最后一段，吞掉所有剩余的路径段
+                if index != pattern_segments.len() - 1 || index > path_segments.len() {
+                    return None;
+                }
+                let decoded: Vec<String> = path_segments[index..].iter().map(|s| percent_decode(s)).collect();
+                params.insert(name.to_string(), decoded.join("/"));
+                return Some(params);
+            }
+
+            let path_seg = path_segments.get(index)?;
+
+            if let Some(name) = pattern_seg.strip_prefix(':') {
+                params.insert(name.to_string(), percent_decode(path_seg));
+            } else if pattern_seg != path_seg {
                 // 字面量段不匹配
                 return None;
             }
         }
-        
+
+        // 没有通配符时，段数必须完全相同
+        if pattern_segments.len() != path_segments.len() {
+            return None;
+        }
+
         Some(params)
     }
-    
+
+    /// 特异性排序键：数值越小优先级越高。没有通配符的模式永远优先于带通配符的，
+    /// 其次参数段越少（即字面量段越多）越优先。
+    /// Specificity sort key: lower sorts first. Patterns without a wildcard always
+    /// outrank ones with a wildcard; among the rest, fewer param segments (i.e.
+    /// more literal segments) outranks more.
+    pub fn specificity(&self) -> (u8, usize) {
+        let has_wildcard = self.pattern.split('/').any(|segment| segment.starts_with('*'));
+        (has_wildcard as u8, self.params.len())
+    }
+
     /// 获取参数名列表
     pub fn param_names(&self) -> &Vec<String> {
         &self.params
     }
-    
+
     /// 获取模式字符串
     pub fn pattern(&self) -> &str {
         &self.pattern
@@ -183,44 +274,120 @@ impl RouteMatcher {
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+
     #[test]
     fn test_route_matcher() {
         let matcher = RouteMatcher::new("/users/:id/posts/:post_id");
-        
+
         // 测试匹配成功
         let params = matcher.matches("/users/123/posts/456").unwrap();
         assert_eq!(params.get("id"), Some(&"123".to_string()));
         assert_eq!(params.get("post_id"), Some(&"456".to_string()));
-        
+
         // 测试匹配失败
         assert!(matcher.matches("/users/123").is_none());
         assert!(matcher.matches("/users/123/posts").is_none());
         assert!(matcher.matches("/posts/123/users/456").is_none());
     }
-    
+
+    #[test]
+    fn test_route_matcher_trailing_wildcard() {
+        let matcher = RouteMatcher::new("/static/*tail");
+
+        let params = matcher.matches("/static/css/app.css").unwrap();
+        assert_eq!(params.get("tail"), Some(&"css/app.css".to_string()));
+
+        let params = matcher.matches("/static/app.js").unwrap();
+        assert_eq!(params.get("tail"), Some(&"app.js".to_string()));
+
+        assert!(matcher.matches("/other/app.js").is_none());
+    }
+
+    #[test]
+    fn test_route_matcher_percent_decodes_captured_segments() {
+        let matcher = RouteMatcher::new("/users/:name");
+        let params = matcher.matches("/users/john%20doe").unwrap();
+        assert_eq!(params.get("name"), Some(&"john doe".to_string()));
+
+        let matcher = RouteMatcher::new("/static/*tail");
+        let params = matcher.matches("/static/a%2Fb/c%20d").unwrap();
+        assert_eq!(params.get("tail"), Some(&"a/b/c d".to_string()));
+    }
+
+
     #[test]
     fn test_router_basic_operations() {
         let mut router = Router::new();
-        
+
         // 测试添加路由
         let handler = RequestHandler::new(|_| {
             Ok(ResponseContext::with_text(crate::core::types::HttpStatus::Ok, "test"))
         });
-        
+
         assert!(router.add_route(HttpMethod::GET, "/test".to_string(), handler).is_ok());
         assert_eq!(router.route_count(), 1);
         assert!(router.has_route(HttpMethod::GET, "/test"));
-        
+
         // 测试重复添加路由
         let handler2 = RequestHandler::new(|_| {
             Ok(ResponseContext::with_text(crate::core::types::HttpStatus::Ok, "test2"))
         });
         assert!(router.add_route(HttpMethod::GET, "/test".to_string(), handler2).is_err());
-        
+
         // 测试移除路由
         assert!(router.remove_route(HttpMethod::GET, "/test").is_ok());
         assert_eq!(router.route_count(), 0);
         assert!(!router.has_route(HttpMethod::GET, "/test"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_router_dynamic_route_params() {
+        let mut router = Router::new();
+
+        let handler = RequestHandler::new(|context| {
+            let id = context.get_path_param("id").cloned().unwrap_or_default();
+            Ok(ResponseContext::with_text(crate::core::types::HttpStatus::Ok, &id))
+        });
+        router.add_route(HttpMethod::GET, "/users/:id".to_string(), handler).unwrap();
+
+        let context = RequestContext::new(HttpMethod::GET, "/users/42".to_string());
+        let response = router.handle_request(&context).unwrap();
+        assert_eq!(response.body_as_string().unwrap(), "42");
+    }
+
+    #[test]
+    fn test_router_prefers_literal_over_dynamic() {
+        let mut router = Router::new();
+
+        router.add_route(
+            HttpMethod::GET,
+            "/users/:id".to_string(),
+            RequestHandler::new(|_| Ok(ResponseContext::with_text(crate::core::types::HttpStatus::Ok, "dynamic"))),
+        ).unwrap();
+        router.add_route(
+            HttpMethod::GET,
+            "/users/me".to_string(),
+            RequestHandler::new(|_| Ok(ResponseContext::with_text(crate::core::types::HttpStatus::Ok, "literal"))),
+        ).unwrap();
+
+        let context = RequestContext::new(HttpMethod::GET, "/users/me".to_string());
+        let response = router.handle_request(&context).unwrap();
+        assert_eq!(response.body_as_string().unwrap(), "literal");
+    }
+
+    #[test]
+    fn test_router_method_not_allowed_vs_not_found() {
+        let mut router = Router::new();
+        router.add_route(
+            HttpMethod::GET,
+            "/users".to_string(),
+            RequestHandler::new(|_| Ok(ResponseContext::with_text(crate::core::types::HttpStatus::Ok, "ok"))),
+        ).unwrap();
+
+        let wrong_method = RequestContext::new(HttpMethod::POST, "/users".to_string());
+        assert!(matches!(router.handle_request(&wrong_method), Err(HushError::MethodNotAllowed)));
+
+        let missing_path = RequestContext::new(HttpMethod::GET, "/missing".to_string());
+        assert!(matches!(router.handle_request(&missing_path), Err(HushError::RouteNotFound)));
+    }
+}
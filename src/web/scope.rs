@@ -0,0 +1,183 @@
+// ============================================================================
+// 路由作用域 | Route Scope
+// ============================================================================
+
+use std::sync::{Arc, Mutex};
+use crate::core::error::{HushError, HushResult};
+use crate::core::types::{HttpMethod, RequestContext, ResponseContext};
+use crate::middleware::core::{MiddlewareChain, MiddlewareContext, MiddlewareResult};
+use super::router::Router;
+use super::handler::RequestHandler;
+
+/// 路由作用域：把一组路由挂在共同的路径前缀下，并附带只对该前缀生效的
+/// 中间件链（例如给 `/api/v1` 单独加鉴权，而不影响其他路由）。
+///
+/// 请求进入作用域后，会先依次经过该作用域自己的中间件，再交给作用域内部的
+/// `Router` 按剥离前缀后的路径匹配。
+/// A route scope groups routes under a common path prefix along with a
+/// middleware chain that only runs for that prefix (e.g. auth applied only to
+/// `/api/v1`, not the rest of the server).
+///
+/// A request that falls under the scope first runs through the scope's own
+/// middlewares, then is dispatched to the scope's internal `Router` matched
+/// against the path with the prefix stripped off.
+pub struct Scope {
+    prefix: String,
+    router: Arc<Mutex<Router>>,
+    middleware_chain: Arc<Mutex<MiddlewareChain>>,
+}
+
+impl Scope {
+    /// 创建新的作用域。前缀会被规范化为以 `/` 开头且不以 `/` 结尾
+    /// （根前缀 `/` 除外）。
+    /// Create a new scope. The prefix is normalized to start with `/` and not
+    /// end with `/` (except the root prefix `/` itself).
+    pub fn new(prefix: &str) -> Self {
+        let mut normalized = if prefix.starts_with('/') {
+            prefix.to_string()
+        } else {
+            format!("/{}", prefix)
+        };
+        if normalized.len() > 1 && normalized.ends_with('/') {
+            normalized.pop();
+        }
+
+        Self {
+            prefix: normalized,
+            router: Arc::new(Mutex::new(Router::new())),
+            middleware_chain: Arc::new(Mutex::new(MiddlewareChain::new())),
+        }
+    }
+
+    /// 获取规范化后的前缀
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// 在作用域内添加路由（路径相对于作用域前缀）
+    pub fn add_route(&self, method: HttpMethod, path: &str, handler: RequestHandler) -> HushResult<()> {
+        let mut router = self.router.lock()
+            .map_err(|_| HushError::InternalError("Failed to acquire scope router lock".to_string()))?;
+        router.add_route(method, path.to_string(), handler)
+    }
+
+    /// 在作用域内添加中间件，只对命中该作用域前缀的请求生效
+    pub fn add_middleware<F>(&self, name: String, handler: F) -> HushResult<()>
+    where
+        F: Fn(&mut MiddlewareContext, Box<dyn Fn(&mut MiddlewareContext) -> HushResult<MiddlewareResult> + Send + Sync>) -> HushResult<MiddlewareResult> + Send + Sync + 'static,
+    {
+        let mut chain = self.middleware_chain.lock()
+            .map_err(|_| HushError::InternalError("Failed to acquire scope middleware lock".to_string()))?;
+        chain.add_function(name, handler);
+        Ok(())
+    }
+
+    /// 若路径落在该作用域前缀之下，返回剥离前缀后的剩余路径（以 `/` 开头）；
+    /// 否则返回 `None`。
+    /// If the path falls under this scope's prefix, return the remainder with
+    /// the prefix stripped (starting with `/`); otherwise `None`.
+    pub fn strip_prefix(&self, path: &str) -> Option<String> {
+        if self.prefix == "/" {
+            return Some(path.to_string());
+        }
+
+        let rest = path.strip_prefix(self.prefix.as_str())?;
+        if rest.is_empty() {
+            Some("/".to_string())
+        } else if rest.starts_with('/') {
+            Some(rest.to_string())
+        } else {
+            // 前缀命中了一个更长的段（如 `/api2` 命中 `/api` 前缀），不算作用域内
+            None
+        }
+    }
+
+    /// 处理落入该作用域的请求：剥离前缀，依次执行作用域自己的中间件，
+    /// 最终交给作用域内部的路由器。
+    /// Handle a request that falls under this scope: strip the prefix, run the
+    /// scope's own middlewares in order, and finally dispatch to the scope's
+    /// internal router.
+    pub(crate) fn handle(&self, context: &RequestContext) -> HushResult<ResponseContext> {
+        let stripped_path = self.strip_prefix(&context.path)
+            .ok_or(HushError::RouteNotFound)?;
+
+        let mut scoped_request = context.clone();
+        scoped_request.path = stripped_path;
+
+        let mut chain = self.middleware_chain.lock()
+            .map_err(|_| HushError::InternalError("Failed to acquire scope middleware lock".to_string()))?
+            .clone();
+
+        let router = Arc::clone(&self.router);
+        chain.add_function("scope_router_handler".to_string(), move |ctx, _next| {
+            match router.lock() {
+                Ok(router) => match router.handle_request(&ctx.request) {
+                    Ok(response) => Ok(MiddlewareResult::Response(response)),
+                    Err(error) => Ok(MiddlewareResult::Error(error)),
+                },
+                Err(_) => Ok(MiddlewareResult::Error(
+                    HushError::InternalError("Failed to acquire scope router lock".to_string())
+                )),
+            }
+        });
+
+        chain.execute(MiddlewareContext::new(scoped_request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::HttpStatus;
+
+    #[test]
+    fn test_scope_prefix_normalization() {
+        assert_eq!(Scope::new("api/v1").prefix(), "/api/v1");
+        assert_eq!(Scope::new("/api/v1/").prefix(), "/api/v1");
+        assert_eq!(Scope::new("/").prefix(), "/");
+    }
+
+    #[test]
+    fn test_scope_strip_prefix() {
+        let scope = Scope::new("/api/v1");
+        assert_eq!(scope.strip_prefix("/api/v1/users"), Some("/users".to_string()));
+        assert_eq!(scope.strip_prefix("/api/v1"), Some("/".to_string()));
+        assert_eq!(scope.strip_prefix("/api/v2/users"), None);
+        assert_eq!(scope.strip_prefix("/api/v10/users"), None);
+    }
+
+    #[test]
+    fn test_scope_handle_dispatches_to_inner_router() {
+        let scope = Scope::new("/api");
+        scope.add_route(
+            HttpMethod::GET,
+            "/users",
+            RequestHandler::new(|_| Ok(ResponseContext::with_text(HttpStatus::Ok, "scoped"))),
+        ).unwrap();
+
+        let context = RequestContext::new(HttpMethod::GET, "/api/users".to_string());
+        let response = scope.handle(&context).unwrap();
+        assert_eq!(response.body_as_string().unwrap(), "scoped");
+    }
+
+    #[test]
+    fn test_scope_middleware_runs_before_router() {
+        let scope = Scope::new("/api");
+        scope.add_middleware("mark".to_string(), |ctx, next| {
+            ctx.request.set_user_data("seen".to_string(), "yes".to_string());
+            next(ctx)
+        }).unwrap();
+        scope.add_route(
+            HttpMethod::GET,
+            "/ping",
+            RequestHandler::new(|context| {
+                let seen = context.get_user_data("seen").cloned().unwrap_or_default();
+                Ok(ResponseContext::with_text(HttpStatus::Ok, &format!("pong, seen={}", seen)))
+            }),
+        ).unwrap();
+
+        let context = RequestContext::new(HttpMethod::GET, "/api/ping".to_string());
+        let response = scope.handle(&context).unwrap();
+        assert_eq!(response.body_as_string().unwrap(), "pong, seen=yes");
+    }
+}
@@ -6,11 +6,15 @@
 use std::sync::{Arc, Mutex};
 use std::thread;
 use actix_web::{web, App, HttpServer};
-use crate::core::error::{HushError, HushResult, set_last_error};
-use crate::core::types::{HttpMethod, RequestContext};
+use crate::core::error::{HushError, HushResult, set_last_error, default_error_response};
+use crate::core::types::{HttpMethod, HttpStatus, RequestContext, ResponseContext};
 use crate::middleware::core::{MiddlewareChain, MiddlewareContext, MiddlewareResult};
 use super::router::Router;
 use super::handler::RequestHandler;
+use super::scope::Scope;
+use super::error_handlers::ErrorHandlers;
+use super::template::TemplateEngine;
+use super::percent_encoding::percent_decode_plus;
 
 /// Web 服务器配置
 #[derive(Debug, Clone)]
@@ -20,6 +24,15 @@ pub struct WebServerConfig {
     pub max_connections: usize,
     pub keep_alive: u64,
     pub request_timeout: u64,
+    /// 优雅关闭时，等待正在处理的连接完成的最长时间（秒）
+    /// How long (in seconds) graceful shutdown waits for in-flight connections to finish
+    pub shutdown_timeout: u64,
+    /// 模板引擎的基目录：`ResponseContext::with_template` 渲染模板文件时，
+    /// 除非按次指定了 `with_template_from` 覆盖目录，否则都从这里解析
+    /// Base directory for the template engine: where
+    /// `ResponseContext::with_template` files are resolved from unless a
+    /// per-render override is given via `with_template_from`
+    pub template_dir: String,
 }
 
 impl Default for WebServerConfig {
@@ -30,26 +43,50 @@ impl Default for WebServerConfig {
             max_connections: 1000,
             keep_alive: 30,
             request_timeout: 30,
+            shutdown_timeout: 30,
+            template_dir: "templates".to_string(),
         }
     }
 }
 
+/// 自定义的"错误 -> 响应"转换函数：在中间件链或路由处理返回 `Err(HushError)`
+/// 时，替代 [`crate::core::error::default_error_response`] 决定具体的状态码
+/// 和响应体，参考自 actix-web 的 `ResponseError` trait
+/// A custom "error -> response" conversion function: when the middleware
+/// chain or a route handler returns `Err(HushError)`, this replaces
+/// [`crate::core::error::default_error_response`] in deciding the concrete
+/// status code and body, modeled on actix-web's `ResponseError` trait
+type ErrorMapperFn = Arc<dyn Fn(&HushError) -> ResponseContext + Send + Sync>;
+
 /// 重构后的 Web 服务器结构体
 pub struct WebServer {
     config: WebServerConfig,
     router: Arc<Mutex<Router>>,
     middleware_chain: Arc<Mutex<MiddlewareChain>>,
+    scopes: Arc<Mutex<Vec<Arc<Scope>>>>,
+    error_handlers: Arc<Mutex<ErrorHandlers>>,
+    error_mapper: Arc<Mutex<Option<ErrorMapperFn>>>,
+    template_engine: Arc<TemplateEngine>,
     is_running: Arc<Mutex<bool>>,
+    server_handle: Arc<Mutex<Option<actix_web::dev::ServerHandle>>>,
+    worker_thread: Arc<Mutex<Option<thread::JoinHandle<()>>>>,
 }
 
 impl WebServer {
     /// 创建新的 Web 服务器实例
     pub fn new(config: WebServerConfig) -> Self {
+        let template_engine = Arc::new(TemplateEngine::new(config.template_dir.clone()));
         Self {
             config,
             router: Arc::new(Mutex::new(Router::new())),
             middleware_chain: Arc::new(Mutex::new(MiddlewareChain::new())),
+            scopes: Arc::new(Mutex::new(Vec::new())),
+            error_handlers: Arc::new(Mutex::new(ErrorHandlers::new())),
+            error_mapper: Arc::new(Mutex::new(None)),
+            template_engine,
             is_running: Arc::new(Mutex::new(false)),
+            server_handle: Arc::new(Mutex::new(None)),
+            worker_thread: Arc::new(Mutex::new(None)),
         }
     }
     
@@ -65,7 +102,14 @@ impl WebServer {
         router.add_route(method, path.to_string(), handler)
     }
     
-    /// 添加中间件
+    /// 添加中间件。默认优先级为 100（数字越小优先级越高），在内置中间件
+    /// （日志 5、CORS 10、限流 15、CSRF 18、认证 20、静态文件 30、安全响应头 90）
+    /// 之后、路由处理（同样默认优先级 100，按注册顺序排在其后）之前执行。
+    /// 需要更早或更晚执行时用 [`Self::add_middleware_with_priority`]
+    /// Add a middleware with the default priority of 100 (lower runs first)
+    /// — after the built-in ones (logger 5, CORS 10, rate-limit 15, CSRF 18,
+    /// auth 20, static files 30, security headers 90) and at/before route
+    /// handling. Use [`Self::add_middleware_with_priority`] to run earlier or later.
     pub fn add_middleware<F>(&self, name: String, handler: F) -> HushResult<()>
     where
         F: Fn(&mut MiddlewareContext, Box<dyn Fn(&mut MiddlewareContext) -> HushResult<MiddlewareResult> + Send + Sync>) -> HushResult<MiddlewareResult> + Send + Sync + 'static,
@@ -75,15 +119,134 @@ impl WebServer {
         chain.add_function(name, handler);
         Ok(())
     }
-    
-    /// 初始化默认中间件（包括路由处理器）
+
+    /// 添加中间件并显式指定优先级（数字越小优先级越高，与内置中间件共享
+    /// 同一个优先级空间），以获得确定的执行顺序，而不是依赖注册顺序
+    /// Add a middleware with an explicit priority (lower runs first, sharing
+    /// the same priority space as the built-in middleware) for deterministic
+    /// ordering instead of relying on registration order
+    pub fn add_middleware_with_priority<F>(&self, name: String, priority: i32, handler: F) -> HushResult<()>
+    where
+        F: Fn(&mut MiddlewareContext, Box<dyn Fn(&mut MiddlewareContext) -> HushResult<MiddlewareResult> + Send + Sync>) -> HushResult<MiddlewareResult> + Send + Sync + 'static,
+    {
+        let mut chain = self.middleware_chain.lock()
+            .map_err(|_| HushError::InternalError("Failed to acquire middleware lock".to_string()))?;
+        chain.add_function_with_priority(name, priority, handler);
+        Ok(())
+    }
+
+    /// 添加一个路径作用域中间件：只有当请求路径以 `prefix` 开头时才会执行，
+    /// 其余请求直接放行给链上的下一个中间件，例如只在 `/api/admin` 下启用认证
+    pub fn add_scoped_middleware<F>(&self, prefix: &str, name: String, handler: F) -> HushResult<()>
+    where
+        F: Fn(&mut MiddlewareContext, Box<dyn Fn(&mut MiddlewareContext) -> HushResult<MiddlewareResult> + Send + Sync>) -> HushResult<MiddlewareResult> + Send + Sync + 'static,
+    {
+        use crate::middleware::core::{FunctionMiddleware, ScopedMiddleware};
+        let mut chain = self.middleware_chain.lock()
+            .map_err(|_| HushError::InternalError("Failed to acquire middleware lock".to_string()))?;
+        let inner = Arc::new(FunctionMiddleware::new(name, handler));
+        chain.add(ScopedMiddleware::new(prefix.to_string(), inner));
+        Ok(())
+    }
+
+    /// 注册一个共享路径前缀的作用域，`configure` 在作用域上添加专属的路由和
+    /// 中间件。落入该前缀下的请求会先经过作用域自己的中间件，再交给作用域
+    /// 内部的路由器，完全不影响作用域之外的请求。
+    ///
+    /// # Example
+    /// ```ignore
+    /// server.scope("/api/v1", |s| {
+    ///     s.add_middleware("auth".to_string(), auth_handler).unwrap();
+    ///     s.add_route(HttpMethod::GET, "/users", handler).unwrap();
+    /// }).unwrap();
+    /// ```
+    pub fn scope<F>(&self, prefix: &str, configure: F) -> HushResult<()>
+    where
+        F: FnOnce(&Scope),
+    {
+        let scope = Arc::new(Scope::new(prefix));
+        configure(&scope);
+
+        let mut scopes = self.scopes.lock()
+            .map_err(|_| HushError::InternalError("Failed to acquire scopes lock".to_string()))?;
+        scopes.push(scope);
+        Ok(())
+    }
+
+    /// 为指定状态码注册自定义错误处理器：链执行或路由处理产生该状态码的响应
+    /// （包括映射到该状态码的错误，如 404/405/500/408）时，`handler` 有机会
+    /// 在响应写回前替换其响应体/响应头。同一状态码再次注册会覆盖之前的处理器
+    pub fn add_error_handler<F>(&self, status: HttpStatus, handler: F) -> HushResult<()>
+    where
+        F: Fn(ResponseContext) -> ResponseContext + Send + Sync + 'static,
+    {
+        let mut error_handlers = self.error_handlers.lock()
+            .map_err(|_| HushError::InternalError("Failed to acquire error handlers lock".to_string()))?;
+        error_handlers.register(status, handler);
+        Ok(())
+    }
+
+    /// 注册一个自定义的"错误 -> 响应"转换函数：中间件链或路由处理返回
+    /// `Err(HushError)` 时，用 `handler` 代替内置的默认映射表
+    /// （[`crate::core::error::default_error_response`]）把错误转换成具体的
+    /// `ResponseContext`。再次调用会覆盖之前注册的函数。注意这与
+    /// [`Self::add_error_handler`] 不同：后者按状态码转换一个已经生成好的
+    /// 响应，而这里是在状态码和响应体生成之前，从原始错误本身决定它们
+    /// Register a custom "error -> response" conversion function: when the
+    /// middleware chain or a route handler returns `Err(HushError)`,
+    /// `handler` replaces the built-in default mapping table
+    /// ([`crate::core::error::default_error_response`]) in turning the error
+    /// into a concrete `ResponseContext`. Calling this again overwrites the
+    /// previously registered function. Note this differs from
+    /// [`Self::add_error_handler`]: that one transforms an already-built
+    /// response keyed by status code, while this one decides the status
+    /// code and body from the raw error itself, before a response exists.
+    pub fn set_error_handler<F>(&self, handler: F) -> HushResult<()>
+    where
+        F: Fn(&HushError) -> ResponseContext + Send + Sync + 'static,
+    {
+        let mut error_mapper = self.error_mapper.lock()
+            .map_err(|_| HushError::InternalError("Failed to acquire error mapper lock".to_string()))?;
+        *error_mapper = Some(Arc::new(handler));
+        Ok(())
+    }
+
+    /// 初始化默认中间件（包括路由处理器）。幂等：若已经初始化过（链中已有
+    /// `router_handler`），直接返回，使其可以被 `start_with_port` 和
+    /// `test_request` 安全地重复调用。
     fn initialize_default_middleware(&self) -> HushResult<()> {
         let mut chain = self.middleware_chain.lock()
             .map_err(|_| HushError::InternalError("Failed to acquire middleware lock".to_string()))?;
-        
-        // 添加路由处理作为最后一个中间件
+
+        if chain.middleware_names().iter().any(|name| name == "router_handler") {
+            return Ok(());
+        }
+
+        // 添加路由处理作为最后一个中间件：先按最长前缀匹配已注册的作用域，
+        // 命中则交给该作用域处理；否则回退到顶层路由器。
         let router_clone = Arc::clone(&self.router);
+        let scopes_clone = Arc::clone(&self.scopes);
         chain.add_function("router_handler".to_string(), move |ctx, _next| {
+            let matched_scope = match scopes_clone.lock() {
+                Ok(scopes) => scopes
+                    .iter()
+                    .filter(|scope| scope.strip_prefix(&ctx.request.path).is_some())
+                    .max_by_key(|scope| scope.prefix().len())
+                    .cloned(),
+                Err(_) => {
+                    return Ok(MiddlewareResult::Error(
+                        HushError::InternalError("Failed to acquire scopes lock".to_string())
+                    ));
+                }
+            };
+
+            if let Some(scope) = matched_scope {
+                return match scope.handle(&ctx.request) {
+                    Ok(response) => Ok(MiddlewareResult::Response(response)),
+                    Err(error) => Ok(MiddlewareResult::Error(error)),
+                };
+            }
+
             match router_clone.lock() {
                 Ok(router) => {
                     match router.handle_request(&ctx.request) {
@@ -96,27 +259,78 @@ impl WebServer {
                 )),
             }
         });
-        
+
         Ok(())
     }
     
-    /// 获取中间件数量
+    /// 获取中间件数量（全局 + 路径作用域）
     pub fn middleware_count(&self) -> usize {
         self.middleware_chain.lock()
             .map(|chain| chain.len())
             .unwrap_or(0)
     }
-    
-    /// 添加 CORS 中间件
-    pub fn add_cors_middleware(&self, allowed_origins: String) -> HushResult<()> {
+
+    /// 获取全局中间件数量（不限路径前缀）
+    pub fn global_middleware_count(&self) -> usize {
+        self.middleware_chain.lock()
+            .map(|chain| chain.global_count())
+            .unwrap_or(0)
+    }
+
+    /// 获取路径作用域中间件数量
+    pub fn scoped_middleware_count(&self) -> usize {
+        self.middleware_chain.lock()
+            .map(|chain| chain.scoped_count())
+            .unwrap_or(0)
+    }
+
+    /// 按实际执行顺序返回链上每个中间件的名字（即按优先级排序后的顺序，数字
+    /// 越小越靠前；同优先级的保持注册的相对顺序），供测试断言确定的执行顺序
+    /// Returns every middleware's name in actual execution order (sorted by
+    /// priority, lower first; ties keep registration order) — for tests to
+    /// assert on deterministic ordering
+    pub fn middleware_order(&self) -> Vec<String> {
+        self.middleware_chain.lock()
+            .map(|chain| chain.middleware_names())
+            .unwrap_or_default()
+    }
+
+    /// 添加 CORS 中间件。`allowed_origins` 为逗号分隔的来源列表或字面量 `*`；
+    /// `allow_credentials` 为真时禁止回显字面量 `*`，改为回显实际请求来源。
+    /// `allowed_origins == "*"` 搭配 `allow_credentials == true` 是浏览器
+    /// 一律拒绝的组合，这里会 fail fast 返回 `ConfigError`
+    /// `allowed_origins == "*"` combined with `allow_credentials == true` is
+    /// a combination every browser refuses — this fails fast with a `ConfigError`
+    pub fn add_cors_middleware(&self, allowed_origins: String, allow_credentials: bool) -> HushResult<()> {
         use crate::middleware::builtin::CorsMiddleware;
         let mut chain = self.middleware_chain.lock()
             .map_err(|_| HushError::InternalError("Failed to acquire middleware lock".to_string()))?;
-        let cors_middleware = CorsMiddleware::new(allowed_origins);
+        let mut cors_middleware = CorsMiddleware::new(allowed_origins);
+        if allow_credentials {
+            cors_middleware = cors_middleware.with_credentials();
+        }
+        let cors_middleware = cors_middleware.finish()?;
         chain.add(cors_middleware);
         Ok(())
     }
-    
+
+    /// 用 [`crate::middleware::builtin::CorsConfig`] 构建器添加一个完整配置的
+    /// CORS 中间件：可分别指定允许的来源、方法、请求头、暴露的响应头、是否
+    /// 允许凭据，以及预检结果缓存时长，不必手写逗号分隔字符串，也不需要
+    /// 再手动注册 OPTIONS 路由来处理预检请求。
+    /// Adds a fully-configured CORS middleware built from a
+    /// [`crate::middleware::builtin::CorsConfig`] builder: lets callers set
+    /// allowed origins, methods, headers, exposed headers, whether
+    /// credentials are allowed, and the preflight cache lifetime separately,
+    /// without hand-writing comma-separated strings or registering an
+    /// OPTIONS route to handle preflight requests.
+    pub fn add_cors_config_middleware(&self, config: crate::middleware::builtin::CorsConfig) -> HushResult<()> {
+        let mut chain = self.middleware_chain.lock()
+            .map_err(|_| HushError::InternalError("Failed to acquire middleware lock".to_string()))?;
+        chain.add(config.build()?);
+        Ok(())
+    }
+
     /// 添加日志中间件
     pub fn add_logger_middleware(&self) -> HushResult<()> {
         use crate::middleware::builtin::LoggerMiddleware;
@@ -126,7 +340,72 @@ impl WebServer {
         chain.add(logger_middleware);
         Ok(())
     }
+
+    /// 添加默认响应头中间件：在响应阶段为尚未设置的响应头补上 `headers` 中的默认值，
+    /// 不会覆盖处理器已设置的值
+    pub fn add_default_headers_middleware(&self, headers: Vec<(String, String)>) -> HushResult<()> {
+        use crate::middleware::builtin::DefaultHeadersMiddleware;
+        let mut chain = self.middleware_chain.lock()
+            .map_err(|_| HushError::InternalError("Failed to acquire middleware lock".to_string()))?;
+        chain.add(DefaultHeadersMiddleware::new(headers));
+        Ok(())
+    }
+
+    /// 添加会话中间件：`store` 为可插拔的会话存储后端（`SessionStore`），
+    /// 按请求携带的 Cookie 加载/持久化会话数据，新建的会话通过 `Set-Cookie`
+    /// 响应头下发生成的 id
+    pub fn add_session_middleware(&self, store: Arc<dyn crate::middleware::session::SessionStore>) -> HushResult<()> {
+        use crate::middleware::session::SessionMiddleware;
+        let mut chain = self.middleware_chain.lock()
+            .map_err(|_| HushError::InternalError("Failed to acquire middleware lock".to_string()))?;
+        chain.add(SessionMiddleware::new(store));
+        Ok(())
+    }
+
+    /// 添加会话中间件，并使用自定义的 Cookie 名称（默认是 `session_id`）
+    pub fn add_session_middleware_with_cookie_name(
+        &self,
+        store: Arc<dyn crate::middleware::session::SessionStore>,
+        cookie_name: &str,
+    ) -> HushResult<()> {
+        use crate::middleware::session::SessionMiddleware;
+        let mut chain = self.middleware_chain.lock()
+            .map_err(|_| HushError::InternalError("Failed to acquire middleware lock".to_string()))?;
+        chain.add(SessionMiddleware::new(store).with_cookie_name(cookie_name));
+        Ok(())
+    }
+
+    /// 添加请求计时中间件：测量请求在链及处理器中花费的时间，
+    /// 在响应头中注入 `X-Response-Time: <ms>ms`
+    pub fn add_timing_middleware(&self) -> HushResult<()> {
+        use crate::middleware::builtin::TimingMiddleware;
+        let mut chain = self.middleware_chain.lock()
+            .map_err(|_| HushError::InternalError("Failed to acquire middleware lock".to_string()))?;
+        let timing_middleware = TimingMiddleware::new();
+        chain.add(timing_middleware);
+        Ok(())
+    }
     
+    /// 添加静态文件服务：把 `url_prefix` 下的请求映射到 `root_dir` 目录中的文件，
+    /// 支持 ETag/Last-Modified 条件请求
+    pub fn add_static_dir(&self, url_prefix: &str, root_dir: &str) -> HushResult<()> {
+        use crate::middleware::builtin::StaticFileMiddleware;
+        let mut chain = self.middleware_chain.lock()
+            .map_err(|_| HushError::InternalError("Failed to acquire middleware lock".to_string()))?;
+        let static_middleware = StaticFileMiddleware::new(url_prefix.to_string(), root_dir.to_string());
+        chain.add(static_middleware);
+        Ok(())
+    }
+
+    /// 添加 CSRF 保护中间件（双重提交 Cookie 模式）
+    pub fn add_csrf_middleware(&self, cookie_name: String, header_name: String) -> HushResult<()> {
+        use crate::middleware::builtin::CsrfMiddleware;
+        let mut chain = self.middleware_chain.lock()
+            .map_err(|_| HushError::InternalError("Failed to acquire middleware lock".to_string()))?;
+        chain.add(CsrfMiddleware::new(cookie_name, header_name));
+        Ok(())
+    }
+
     /// 添加 JWT 认证中间件
     pub fn add_auth_middleware(&self, secret: String) -> HushResult<()> {
         use crate::middleware::builtin::AuthMiddleware;
@@ -161,50 +440,96 @@ impl WebServer {
         config.port = port; // 使用传入的端口参数
         let router = Arc::clone(&self.router);
         let middleware_chain = Arc::clone(&self.middleware_chain);
+        let error_handlers = Arc::clone(&self.error_handlers);
+        let error_mapper = Arc::clone(&self.error_mapper);
+        let template_engine = Arc::clone(&self.template_engine);
         let is_running = Arc::clone(&self.is_running);
-        
+        let server_handle = Arc::clone(&self.server_handle);
+        let request_timeout = config.request_timeout;
+        let shutdown_timeout = config.shutdown_timeout;
+
         // 在新线程中启动服务器
-        thread::spawn(move || {
+        let join_handle = thread::spawn(move || {
             let rt = tokio::runtime::Runtime::new().unwrap();
             rt.block_on(async {
                 println!("Starting Hush web server on {}:{}", config.host, config.port);
-                
+
                 let server_result = HttpServer::new(move || {
                     let router_clone = Arc::clone(&router);
                     let middleware_clone = Arc::clone(&middleware_chain);
+                    let error_handlers_clone = Arc::clone(&error_handlers);
+                    let error_mapper_clone = Arc::clone(&error_mapper);
+                    let template_engine_clone = Arc::clone(&template_engine);
                     App::new()
                         .app_data(web::Data::new(router_clone))
                         .app_data(web::Data::new(middleware_clone))
+                        .app_data(web::Data::new(error_handlers_clone))
+                        .app_data(web::Data::new(error_mapper_clone))
+                        .app_data(web::Data::new(template_engine_clone))
+                        .app_data(web::Data::new(request_timeout))
                         .default_service(web::route().to(Self::handle_request))
                 })
+                .shutdown_timeout(shutdown_timeout)
                 .bind((config.host.as_str(), config.port));
-                
+
                 match server_result {
                     Ok(server) => {
-                        if let Err(e) = server.run().await {
+                        let running_server = server.run();
+                        if let Ok(mut handle_slot) = server_handle.lock() {
+                            *handle_slot = Some(running_server.handle());
+                        }
+
+                        if let Err(e) = running_server.await {
                             set_last_error(HushError::HttpError(format!("Server runtime error: {}", e)));
-                            // 标记服务器为未运行状态
-                            if let Ok(mut running) = is_running.lock() {
-                                *running = false;
-                            }
                         }
                     }
                     Err(e) => {
                         set_last_error(HushError::HttpError(format!("Failed to bind server: {}", e)));
-                        // 标记服务器为未运行状态
-                        if let Ok(mut running) = is_running.lock() {
-                            *running = false;
-                        }
                     }
                 }
+
+                // 服务器已停止运行（无论是正常关闭还是绑定失败）
+                if let Ok(mut running) = is_running.lock() {
+                    *running = false;
+                }
+                if let Ok(mut handle_slot) = server_handle.lock() {
+                    *handle_slot = None;
+                }
             });
         });
-        
+
+        if let Ok(mut worker_slot) = self.worker_thread.lock() {
+            *worker_slot = Some(join_handle);
+        }
+
         Ok(())
     }
-    
-    /// 停止服务器
+
+    /// 停止服务器：触发 actix 的优雅关闭（等待 `shutdown_timeout` 排空连接），
+    /// 并等待工作线程退出
+    /// Stop the server: trigger actix's graceful shutdown (draining connections
+    /// for up to `shutdown_timeout`) and join the worker thread
     pub fn stop(&self) -> HushResult<()> {
+        let handle = self.server_handle.lock()
+            .map_err(|_| HushError::InternalError("Failed to acquire server handle lock".to_string()))?
+            .take();
+
+        if let Some(handle) = handle {
+            // ServerHandle::stop 是异步的，但本方法是同步 FFI 友好接口，
+            // 用一个临时 runtime 在当前线程上阻塞等待它完成
+            let rt = tokio::runtime::Runtime::new()
+                .map_err(|e| HushError::InternalError(format!("Failed to create shutdown runtime: {}", e)))?;
+            rt.block_on(handle.stop(true));
+        }
+
+        let worker = self.worker_thread.lock()
+            .map_err(|_| HushError::InternalError("Failed to acquire worker thread lock".to_string()))?
+            .take();
+
+        if let Some(worker) = worker {
+            let _ = worker.join();
+        }
+
         let mut running = self.is_running.lock()
             .map_err(|_| HushError::InternalError("Failed to acquire running lock".to_string()))?;
         *running = false;
@@ -222,6 +547,25 @@ impl WebServer {
     pub fn config(&self) -> &WebServerConfig {
         &self.config
     }
+
+    /// 在不绑定 TCP 端口的情况下，同步地跑一遍与 [`Self::handle_request`] 相同的
+    /// 路由 + 中间件流水线，供测试使用（参见 [`super::testing`]）。
+    /// Run the same router + middleware pipeline as [`Self::handle_request`]
+    /// synchronously, without binding a TCP socket — for use in tests (see
+    /// [`super::testing`]).
+    pub fn test_request(&self, request: super::testing::TestRequest) -> HushResult<ResponseContext> {
+        self.initialize_default_middleware()?;
+
+        let middleware_context = MiddlewareContext::new(request.into_context());
+        let chain = self.middleware_chain.lock()
+            .map_err(|_| HushError::InternalError("Failed to acquire middleware lock".to_string()))?;
+        let response = chain.execute(middleware_context)?;
+        let response = self.template_engine.apply(response)?;
+
+        let error_handlers = self.error_handlers.lock()
+            .map_err(|_| HushError::InternalError("Failed to acquire error handlers lock".to_string()))?;
+        Ok(error_handlers.apply(response))
+    }
     
     /// 处理 HTTP 请求的核心函数
     async fn handle_request(
@@ -229,6 +573,10 @@ impl WebServer {
         body: actix_web::web::Bytes,
         router_data: web::Data<Arc<Mutex<Router>>>,
         middleware_data: web::Data<Arc<Mutex<MiddlewareChain>>>,
+        error_handlers_data: web::Data<Arc<Mutex<ErrorHandlers>>>,
+        error_mapper_data: web::Data<Arc<Mutex<Option<ErrorMapperFn>>>>,
+        template_engine_data: web::Data<Arc<TemplateEngine>>,
+        request_timeout: web::Data<u64>,
     ) -> actix_web::HttpResponse {
         // 解析 HTTP 方法
         let method = match HttpMethod::from_str(req.method().as_str()) {
@@ -250,11 +598,11 @@ impl WebServer {
             }
         }
         
-        // 解析查询参数
+        // 解析查询参数（`+` 视为空格，`%XX` 解码，与 application/x-www-form-urlencoded 一致）
         for (key, value) in req.query_string().split('&').filter_map(|pair| {
             let mut parts = pair.split('=');
             match (parts.next(), parts.next()) {
-                (Some(k), Some(v)) => Some((k.to_string(), v.to_string())),
+                (Some(k), Some(v)) => Some((percent_decode_plus(k), percent_decode_plus(v))),
                 _ => None,
             }
         }) {
@@ -263,42 +611,76 @@ impl WebServer {
         
         // 创建中间件上下文
         let middleware_context = MiddlewareContext::new(context);
-        
-        // 获取中间件链并执行
-        match middleware_data.lock() {
-            Ok(chain) => {
-                // 执行中间件链（路由处理器已经在初始化时添加）
-                match chain.execute(middleware_context) {
-                    Ok(response) => {
-                        let mut http_response = actix_web::HttpResponse::build(
-                            actix_web::http::StatusCode::from_u16(response.status.as_u16())
-                                .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR)
-                        );
-                        
-                        // 添加响应头
-                        for (key, value) in &response.headers {
-                            http_response.insert_header((key.as_str(), value.as_str()));
-                        }
-                        
-                        http_response.body(response.body.clone())
-                    }
-                    Err(HushError::RouteNotFound) => {
-                        actix_web::HttpResponse::NotFound().body("Route not found")
-                    }
-                    Err(HushError::MethodNotAllowed) => {
-                        actix_web::HttpResponse::MethodNotAllowed().body("Method not allowed")
-                    }
-                    Err(error) => {
-                        set_last_error(error);
-                        actix_web::HttpResponse::InternalServerError()
-                            .body("Internal server error")
-                    }
-                }
+
+        // 在阻塞线程池中执行中间件链（避免长时间持有锁阻塞异步运行时），
+        // 并用 `request_timeout` 包裹整个执行过程：超时则返回 408
+        let middleware_chain = Arc::clone(&middleware_data);
+        let timeout_duration = std::time::Duration::from_secs(*request_timeout.get_ref());
+
+        let execution = tokio::time::timeout(
+            timeout_duration,
+            tokio::task::spawn_blocking(move || {
+                middleware_chain.lock()
+                    .map_err(|_| HushError::InternalError("Failed to acquire middleware lock".to_string()))
+                    .and_then(|chain| chain.execute(middleware_context))
+            }),
+        ).await;
+
+        // 先把链执行结果（无论成功还是映射到状态码的错误）统一归一化为
+        // `ResponseContext`，再交给已注册的错误处理器（如果有）转换，最后
+        // 一次性转换为 actix 的 `HttpResponse`
+        let response_context = match execution {
+            Ok(Ok(Ok(response))) => response,
+            Ok(Ok(Err(error))) => {
+                let response = Self::resolve_error(error_mapper_data.get_ref(), &error);
+                set_last_error(error);
+                response
             }
-            Err(_) => {
-                actix_web::HttpResponse::InternalServerError()
-                    .body("Failed to acquire middleware lock")
+            Ok(Err(_join_error)) => {
+                ResponseContext::with_text(HttpStatus::InternalServerError, "Middleware chain task panicked")
+            }
+            Err(_elapsed) => {
+                set_last_error(HushError::RequestTimeout);
+                ResponseContext::with_text(HttpStatus::RequestTimeout, "Request timeout")
+            }
+        };
+
+        let response_context = match template_engine_data.get_ref().apply(response_context) {
+            Ok(rendered) => rendered,
+            Err(error) => {
+                set_last_error(error);
+                ResponseContext::with_text(HttpStatus::InternalServerError, "Template rendering failed")
             }
+        };
+
+        let response_context = match error_handlers_data.get_ref().lock() {
+            Ok(error_handlers) => error_handlers.apply(response_context),
+            Err(_) => response_context,
+        };
+
+        let mut http_response = actix_web::HttpResponse::build(
+            actix_web::http::StatusCode::from_u16(response_context.status.as_u16())
+                .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR)
+        );
+
+        // 添加响应头
+        for (key, value) in &response_context.headers {
+            http_response.insert_header((key.as_str(), value.as_str()));
+        }
+
+        http_response.body(response_context.body.clone())
+    }
+
+    /// 供 [`Self::handle_request`]（没有 `self`，只有 `web::Data` 句柄）使用的
+    /// 静态辅助函数：用已注册的自定义错误转换函数（如果有）
+    /// 或内置默认映射表，把一个 `HushError` 转换成 `ResponseContext`
+    fn resolve_error(error_mapper: &Arc<Mutex<Option<ErrorMapperFn>>>, error: &HushError) -> ResponseContext {
+        match error_mapper.lock() {
+            Ok(mapper) => match mapper.as_ref() {
+                Some(handler) => handler(error),
+                None => default_error_response(error),
+            },
+            Err(_) => default_error_response(error),
         }
     }
 }
\ No newline at end of file
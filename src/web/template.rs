@@ -0,0 +1,263 @@
+// ============================================================================
+// 模板渲染引擎 | Template Rendering Engine
+// ============================================================================
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use crate::core::error::{HushError, HushResult};
+use crate::core::types::{ResponseContext, TemplateValue};
+
+/// 轻量级 Mustache 风格模板引擎：从配置的基目录加载命名模板文件，替换
+/// `{{name}}` 占位符，并根据上下文中的 `{{#section}}...{{/section}}` 区块
+/// 重复渲染（列表）或决定是否渲染（布尔值）
+/// A lightweight Mustache-style template engine: loads a named template file
+/// from the configured base directory, substitutes `{{name}}` placeholders,
+/// and either repeats (for a list value) or conditionally renders (for a
+/// boolean value) `{{#section}}...{{/section}}` blocks
+#[derive(Debug, Clone)]
+pub struct TemplateEngine {
+    base_dir: PathBuf,
+}
+
+impl TemplateEngine {
+    pub fn new(base_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            base_dir: base_dir.into(),
+        }
+    }
+
+    /// 使用引擎配置的基目录渲染 `name` 指定的模板文件
+    pub fn render(&self, name: &str, context: &HashMap<String, TemplateValue>) -> HushResult<String> {
+        self.render_from(name, context, None)
+    }
+
+    /// 渲染 `name` 指定的模板文件，`base_override` 非空时覆盖引擎配置的基目录
+    pub fn render_from(
+        &self,
+        name: &str,
+        context: &HashMap<String, TemplateValue>,
+        base_override: Option<&str>,
+    ) -> HushResult<String> {
+        let base = base_override
+            .map(PathBuf::from)
+            .unwrap_or_else(|| self.base_dir.clone());
+        let template = fs::read_to_string(base.join(name))
+            .map_err(|_| HushError::FileNotFound)?;
+        Ok(render_str(&template, context))
+    }
+
+    /// 渲染响应中待渲染的模板（如果有），写回响应体并设置
+    /// `Content-Type: text/html`；没有待渲染模板的响应原样返回
+    pub fn apply(&self, mut response: ResponseContext) -> HushResult<ResponseContext> {
+        let pending = match response.take_pending_template() {
+            Some(pending) => pending,
+            None => return Ok(response),
+        };
+
+        let rendered = self.render_from(&pending.name, &pending.context, pending.base_override.as_deref())?;
+        response.set_body(rendered.into_bytes());
+        response.add_header("Content-Type".to_string(), "text/html".to_string());
+        Ok(response)
+    }
+}
+
+/// 先展开 `{{#section}}` 区块，再替换剩余的 `{{name}}` 占位符
+fn render_str(template: &str, context: &HashMap<String, TemplateValue>) -> String {
+    let expanded = render_sections(template, context);
+    render_placeholders(&expanded, context)
+}
+
+/// 把每个 `{{#name}}...{{/name}}` 区块替换为其展开结果：列表值按项重复渲染，
+/// 布尔值决定是否渲染一次，其余值（或缺失的键）渲染一次/跳过
+fn render_sections(template: &str, context: &HashMap<String, TemplateValue>) -> String {
+    let mut output = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{#") {
+        output.push_str(&rest[..start]);
+        let after_tag = &rest[start + 3..];
+
+        let name_end = match after_tag.find("}}") {
+            Some(idx) => idx,
+            None => {
+                output.push_str(&rest[start..]);
+                return output;
+            }
+        };
+        let section_name = after_tag[..name_end].trim();
+        let body_start = start + 3 + name_end + 2;
+        let close_tag = format!("{{{{/{}}}}}", section_name);
+        let body_rest = &rest[body_start..];
+
+        let close_idx = match body_rest.find(&close_tag) {
+            Some(idx) => idx,
+            None => {
+                output.push_str(&rest[start..]);
+                return output;
+            }
+        };
+
+        let block = &body_rest[..close_idx];
+        output.push_str(&render_section(section_name, block, context));
+        rest = &body_rest[close_idx + close_tag.len()..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+fn render_section(name: &str, block: &str, context: &HashMap<String, TemplateValue>) -> String {
+    match context.get(name) {
+        Some(TemplateValue::List(items)) => items
+            .iter()
+            .map(|item| render_str(block, item))
+            .collect::<Vec<_>>()
+            .join(""),
+        Some(TemplateValue::Bool(enabled)) => {
+            if *enabled { render_str(block, context) } else { String::new() }
+        }
+        Some(TemplateValue::Text(_)) => render_str(block, context),
+        None => String::new(),
+    }
+}
+
+/// 替换剩余的 `{{name}}` 占位符（`Text` 值原样插入，其余类型或缺失键替换为空）
+fn render_placeholders(template: &str, context: &HashMap<String, TemplateValue>) -> String {
+    let mut output = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after_open = &rest[start + 2..];
+
+        let end = match after_open.find("}}") {
+            Some(idx) => idx,
+            None => {
+                output.push_str(&rest[start..]);
+                return output;
+            }
+        };
+
+        let key = after_open[..end].trim();
+        if let Some(TemplateValue::Text(value)) = context.get(key) {
+            output.push_str(value);
+        }
+        rest = &after_open[end + 2..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::HttpStatus;
+
+    fn template_test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("hush_template_test_{}_{}", name, std::process::id()));
+        let _ = fs::create_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_render_substitutes_placeholders() {
+        let mut context = HashMap::new();
+        context.insert("name".to_string(), TemplateValue::Text("World".to_string()));
+
+        let rendered = render_str("Hello, {{name}}!", &context);
+        assert_eq!(rendered, "Hello, World!");
+    }
+
+    #[test]
+    fn test_render_skips_missing_placeholder() {
+        let context = HashMap::new();
+        let rendered = render_str("Hello, {{name}}!", &context);
+        assert_eq!(rendered, "Hello, !");
+    }
+
+    #[test]
+    fn test_render_repeats_section_for_each_list_item() {
+        let mut item1 = HashMap::new();
+        item1.insert("title".to_string(), TemplateValue::Text("First".to_string()));
+        let mut item2 = HashMap::new();
+        item2.insert("title".to_string(), TemplateValue::Text("Second".to_string()));
+
+        let mut context = HashMap::new();
+        context.insert("posts".to_string(), TemplateValue::List(vec![item1, item2]));
+
+        let rendered = render_str("{{#posts}}<li>{{title}}</li>{{/posts}}", &context);
+        assert_eq!(rendered, "<li>First</li><li>Second</li>");
+    }
+
+    #[test]
+    fn test_render_bool_section_toggles_block() {
+        let mut context = HashMap::new();
+        context.insert("show".to_string(), TemplateValue::Bool(false));
+
+        let rendered = render_str("before{{#show}}hidden{{/show}}after", &context);
+        assert_eq!(rendered, "beforeafter");
+
+        context.insert("show".to_string(), TemplateValue::Bool(true));
+        let rendered = render_str("before{{#show}}shown{{/show}}after", &context);
+        assert_eq!(rendered, "beforeshownafter");
+    }
+
+    #[test]
+    fn test_engine_render_loads_from_base_dir() {
+        let dir = template_test_dir("render");
+        fs::write(dir.join("greeting.html"), "Hi, {{name}}!").unwrap();
+        let engine = TemplateEngine::new(dir);
+
+        let mut context = HashMap::new();
+        context.insert("name".to_string(), TemplateValue::Text("Ada".to_string()));
+
+        let rendered = engine.render("greeting.html", &context).unwrap();
+        assert_eq!(rendered, "Hi, Ada!");
+    }
+
+    #[test]
+    fn test_engine_render_from_overrides_base_dir() {
+        let default_dir = template_test_dir("default");
+        let override_dir = template_test_dir("override");
+        fs::write(override_dir.join("page.html"), "overridden").unwrap();
+        let engine = TemplateEngine::new(default_dir);
+
+        let rendered = engine
+            .render_from("page.html", &HashMap::new(), Some(override_dir.to_str().unwrap()))
+            .unwrap();
+        assert_eq!(rendered, "overridden");
+    }
+
+    #[test]
+    fn test_engine_render_missing_template_is_file_not_found() {
+        let engine = TemplateEngine::new(template_test_dir("missing"));
+        let result = engine.render("does-not-exist.html", &HashMap::new());
+        assert!(matches!(result, Err(HushError::FileNotFound)));
+    }
+
+    #[test]
+    fn test_apply_renders_pending_template_and_sets_content_type() {
+        let dir = template_test_dir("apply");
+        fs::write(dir.join("index.html"), "Welcome, {{user}}!").unwrap();
+        let engine = TemplateEngine::new(dir);
+
+        let mut context = HashMap::new();
+        context.insert("user".to_string(), TemplateValue::Text("Grace".to_string()));
+        let response = ResponseContext::with_template("index.html", context);
+
+        let rendered = engine.apply(response).unwrap();
+        assert_eq!(rendered.status.as_u16(), HttpStatus::Ok.as_u16());
+        assert_eq!(rendered.body_as_string().unwrap(), "Welcome, Grace!");
+        assert_eq!(rendered.headers.get("Content-Type"), Some(&"text/html".to_string()));
+    }
+
+    #[test]
+    fn test_apply_passes_through_response_without_pending_template() {
+        let engine = TemplateEngine::new(template_test_dir("no_pending"));
+        let response = ResponseContext::with_text(HttpStatus::Ok, "plain");
+        let result = engine.apply(response).unwrap();
+        assert_eq!(result.body_as_string().unwrap(), "plain");
+    }
+}
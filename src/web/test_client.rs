@@ -0,0 +1,212 @@
+// ============================================================================
+// 真实网络测试客户端 | Real-Socket Test Client
+// ============================================================================
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use crate::core::error::{HushError, HushResult};
+use crate::core::types::HttpMethod;
+
+/// 对一次真实 HTTP 响应的解析结果：状态码、响应头（保留原始大小写、
+/// 按首次出现顺序排列）和响应体。
+/// A parsed real HTTP response: status code, response headers (original
+/// casing preserved, in first-seen order) and the body.
+pub struct TestResponse {
+    pub status: u16,
+    headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl TestResponse {
+    /// 按不区分大小写的名称查找第一个匹配的响应头
+    /// Looks up the first response header matching `name`, case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn body_as_string(&self) -> HushResult<String> {
+        String::from_utf8(self.body.clone())
+            .map_err(|e| HushError::HttpError(format!("Response body is not valid UTF-8: {}", e)))
+    }
+}
+
+/// 面向真实 TCP 连接的最小 HTTP/1.1 客户端：向 `127.0.0.1:<port>` 发起
+/// GET/POST/OPTIONS 等请求，解析出状态码、响应头和响应体，让端到端测试
+/// 真正验证中间件和处理器在网络上的行为，而不是只检查服务器是否在运行。
+/// 仓库没有引入 HTTP 客户端依赖，因此和 [`crate::middleware::session::SignedCookieBackend`]
+/// 等其它网络/加密相关能力一样，这里手写一个足够用的最小实现。
+///
+/// A minimal HTTP/1.1 client over a real TCP socket: issues GET/POST/OPTIONS
+/// (and other) requests to `127.0.0.1:<port>` and parses out the status,
+/// headers, and body, so end-to-end tests can actually assert on what comes
+/// back over the wire instead of only checking that the server is running.
+/// The repo pulls in no HTTP client dependency, so — like
+/// [`crate::middleware::session::SignedCookieBackend`] and other
+/// network/crypto-adjacent pieces — this is a small hand-rolled
+/// implementation, good enough for tests rather than general-purpose use.
+pub struct TestClient {
+    port: u16,
+    timeout: Duration,
+}
+
+impl TestClient {
+    pub fn new(port: u16) -> Self {
+        Self {
+            port,
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn get(&self, path: &str) -> HushResult<TestResponse> {
+        self.request(HttpMethod::GET, path, &[], None)
+    }
+
+    pub fn post(&self, path: &str, body: &[u8]) -> HushResult<TestResponse> {
+        self.request(HttpMethod::POST, path, &[], Some(body))
+    }
+
+    pub fn options(&self, path: &str) -> HushResult<TestResponse> {
+        self.request(HttpMethod::OPTIONS, path, &[], None)
+    }
+
+    /// 发起一次带自定义请求头的请求
+    /// Issues a request with custom headers attached
+    pub fn request_with_headers(
+        &self,
+        method: HttpMethod,
+        path: &str,
+        headers: &[(&str, &str)],
+        body: Option<&[u8]>,
+    ) -> HushResult<TestResponse> {
+        self.request(method, path, headers, body)
+    }
+
+    fn request(
+        &self,
+        method: HttpMethod,
+        path: &str,
+        headers: &[(&str, &str)],
+        body: Option<&[u8]>,
+    ) -> HushResult<TestResponse> {
+        let address = format!("127.0.0.1:{}", self.port);
+        let mut stream = TcpStream::connect(&address)
+            .map_err(|e| HushError::HttpError(format!("Failed to connect to {}: {}", address, e)))?;
+        stream.set_read_timeout(Some(self.timeout)).ok();
+        stream.set_write_timeout(Some(self.timeout)).ok();
+
+        let body = body.unwrap_or(&[]);
+        let mut request = format!(
+            "{} {} HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nConnection: close\r\n",
+            method.as_str(),
+            path,
+            self.port
+        );
+        for (name, value) in headers {
+            request.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        if !body.is_empty() {
+            request.push_str(&format!("Content-Length: {}\r\n", body.len()));
+        }
+        request.push_str("\r\n");
+
+        stream
+            .write_all(request.as_bytes())
+            .map_err(|e| HushError::HttpError(format!("Failed to write request: {}", e)))?;
+        if !body.is_empty() {
+            stream
+                .write_all(body)
+                .map_err(|e| HushError::HttpError(format!("Failed to write request body: {}", e)))?;
+        }
+
+        let mut raw = Vec::new();
+        stream
+            .read_to_end(&mut raw)
+            .map_err(|e| HushError::HttpError(format!("Failed to read response: {}", e)))?;
+
+        parse_response(&raw)
+    }
+}
+
+fn parse_response(raw: &[u8]) -> HushResult<TestResponse> {
+    let separator = b"\r\n\r\n";
+    let split_at = raw
+        .windows(separator.len())
+        .position(|window| window == separator)
+        .ok_or_else(|| HushError::HttpError("Response is missing a header/body separator".to_string()))?;
+
+    let head = std::str::from_utf8(&raw[..split_at])
+        .map_err(|e| HushError::HttpError(format!("Response headers are not valid UTF-8: {}", e)))?;
+    let body = raw[split_at + separator.len()..].to_vec();
+
+    let mut lines = head.split("\r\n");
+    let status_line = lines
+        .next()
+        .ok_or_else(|| HushError::HttpError("Response is missing a status line".to_string()))?;
+    let status = status_line
+        .splitn(3, ' ')
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| HushError::HttpError(format!("Malformed status line: {}", status_line)))?;
+
+    let mut parsed_headers = Vec::new();
+    let mut header_map = HashMap::new();
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':') {
+            let name = name.trim().to_string();
+            let value = value.trim().to_string();
+            header_map.insert(name.to_ascii_lowercase(), value.clone());
+            parsed_headers.push((name, value));
+        }
+    }
+
+    let body = if let Some(length) = header_map.get("content-length").and_then(|v| v.parse::<usize>().ok()) {
+        body.into_iter().take(length).collect()
+    } else {
+        body
+    };
+
+    Ok(TestResponse {
+        status,
+        headers: parsed_headers,
+        body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response_reads_status_headers_and_body() {
+        let raw = b"HTTP/1.1 200 OK\r\nX-Powered-By: Hush Framework\r\nContent-Length: 5\r\n\r\nhello";
+        let response = parse_response(raw).unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.header("x-powered-by"), Some("Hush Framework"));
+        assert_eq!(response.body_as_string().unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_parse_response_truncates_body_to_content_length() {
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nhello";
+        let response = parse_response(raw).unwrap();
+        assert_eq!(response.body_as_string().unwrap(), "he");
+    }
+
+    #[test]
+    fn test_parse_response_rejects_missing_separator() {
+        let raw = b"HTTP/1.1 200 OK\r\nX-Powered-By: Hush Framework";
+        assert!(parse_response(raw).is_err());
+    }
+}
@@ -0,0 +1,357 @@
+// ============================================================================
+// 进程内测试工具 | In-Process Testing Utilities
+// ============================================================================
+
+use std::collections::HashMap;
+use crate::core::error::HushResult;
+use crate::core::types::{HttpMethod, RequestContext, ResponseContext};
+use super::server::WebServer;
+
+/// 构造一个 `RequestContext` 的测试请求构建器（方法、路径、请求头、查询参数、
+/// 请求体、Cookie），交给 [`TestServer`] 或 [`WebServer::test_request`] 驱动
+/// 完整的路由 + 中间件流水线，而不需要绑定真实的 TCP 端口。
+/// Builds a `RequestContext` (method, path, headers, query params, body,
+/// cookies) for exercising the full router + middleware pipeline through
+/// [`TestServer`] or [`WebServer::test_request`] without binding a real
+/// TCP socket.
+pub struct TestRequest {
+    method: HttpMethod,
+    path: String,
+    headers: HashMap<String, String>,
+    query_params: HashMap<String, String>,
+    body: Vec<u8>,
+    cookies: Vec<(String, String)>,
+}
+
+impl TestRequest {
+    pub fn new(method: HttpMethod, path: &str) -> Self {
+        Self {
+            method,
+            path: path.to_string(),
+            headers: HashMap::new(),
+            query_params: HashMap::new(),
+            body: Vec::new(),
+            cookies: Vec::new(),
+        }
+    }
+
+    pub fn get(path: &str) -> Self {
+        Self::new(HttpMethod::GET, path)
+    }
+
+    pub fn post(path: &str) -> Self {
+        Self::new(HttpMethod::POST, path)
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    pub fn query(mut self, key: &str, value: &str) -> Self {
+        self.query_params.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn body(mut self, body: Vec<u8>) -> Self {
+        self.body = body;
+        self
+    }
+
+    pub fn text_body(self, body: &str) -> Self {
+        self.body(body.as_bytes().to_vec())
+    }
+
+    pub fn cookie(mut self, name: &str, value: &str) -> Self {
+        self.cookies.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// 转换为路由/中间件流水线实际使用的 `RequestContext`
+    pub fn into_context(self) -> RequestContext {
+        let mut context = RequestContext::new(self.method, self.path);
+
+        for (key, value) in self.headers {
+            context.add_header(key, value);
+        }
+
+        for (key, value) in self.query_params {
+            context.add_query_param(key, value);
+        }
+
+        if !self.cookies.is_empty() {
+            let cookie_header = self.cookies
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect::<Vec<_>>()
+                .join("; ");
+            context.add_header("Cookie".to_string(), cookie_header);
+        }
+
+        context.set_body(self.body);
+        context
+    }
+}
+
+/// 对 `WebServer` 的薄包装，用于进程内测试：不绑定端口，直接同步驱动
+/// 路由 + 中间件流水线。
+/// A thin wrapper around `WebServer` for in-process testing: no socket is
+/// bound, requests are driven synchronously through the router + middleware
+/// pipeline.
+pub struct TestServer {
+    server: WebServer,
+}
+
+impl TestServer {
+    pub fn new(server: WebServer) -> Self {
+        Self { server }
+    }
+
+    /// 驱动一次测试请求，返回流水线产生的响应
+    pub fn call(&self, request: TestRequest) -> HushResult<ResponseContext> {
+        self.server.test_request(request)
+    }
+
+    /// 获取内部的 `WebServer`，以便在测试中继续添加路由/中间件
+    pub fn server(&self) -> &WebServer {
+        &self.server
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::handler::RequestHandler;
+    use crate::web::server::WebServerConfig;
+    use crate::core::types::HttpStatus;
+    use crate::middleware::core::MiddlewareResult;
+
+    fn test_server() -> TestServer {
+        TestServer::new(WebServer::new(WebServerConfig::default()))
+    }
+
+    fn test_server_with_templates(dir: &std::path::Path) -> TestServer {
+        let mut config = WebServerConfig::default();
+        config.template_dir = dir.to_string_lossy().to_string();
+        TestServer::new(WebServer::new(config))
+    }
+
+    #[test]
+    fn test_request_builder_produces_expected_context() {
+        let context = TestRequest::get("/users")
+            .query("id", "42")
+            .header("X-Trace", "abc")
+            .cookie("session", "xyz")
+            .into_context();
+
+        assert_eq!(context.method, HttpMethod::GET);
+        assert_eq!(context.path, "/users");
+        assert_eq!(context.get_query_param("id"), Some(&"42".to_string()));
+        assert_eq!(context.get_header("X-Trace"), Some(&"abc".to_string()));
+        assert_eq!(context.get_header("Cookie"), Some(&"session=xyz".to_string()));
+    }
+
+    #[test]
+    fn test_server_dispatches_to_registered_route() {
+        let test_server = test_server();
+        test_server.server().add_route(
+            HttpMethod::GET,
+            "/ping",
+            RequestHandler::new(|_| Ok(ResponseContext::with_text(HttpStatus::Ok, "pong"))),
+        ).unwrap();
+
+        let response = test_server.call(TestRequest::get("/ping")).unwrap();
+        assert_eq!(response.status.as_u16(), 200);
+        assert_eq!(response.body_as_string().unwrap(), "pong");
+    }
+
+    #[test]
+    fn test_server_returns_404_for_unknown_route() {
+        let test_server = test_server();
+        let result = test_server.call(TestRequest::get("/missing"));
+        assert!(matches!(result, Err(crate::core::error::HushError::RouteNotFound)));
+    }
+
+    #[test]
+    fn test_server_returns_405_for_wrong_method() {
+        let test_server = test_server();
+        test_server.server().add_route(
+            HttpMethod::GET,
+            "/only-get",
+            RequestHandler::new(|_| Ok(ResponseContext::with_text(HttpStatus::Ok, "ok"))),
+        ).unwrap();
+
+        let result = test_server.call(TestRequest::post("/only-get"));
+        assert!(matches!(result, Err(crate::core::error::HushError::MethodNotAllowed)));
+    }
+
+    #[test]
+    fn test_middleware_short_circuits_before_handler() {
+        let test_server = test_server();
+        test_server.server().add_middleware("deny_all".to_string(), |_ctx, _next| {
+            Ok(MiddlewareResult::Response(
+                ResponseContext::with_text(HttpStatus::Forbidden, "denied"),
+            ))
+        }).unwrap();
+        test_server.server().add_route(
+            HttpMethod::GET,
+            "/secret",
+            RequestHandler::new(|_| Ok(ResponseContext::with_text(HttpStatus::Ok, "should not run"))),
+        ).unwrap();
+
+        let response = test_server.call(TestRequest::get("/secret")).unwrap();
+        assert_eq!(response.status.as_u16(), 403);
+        assert_eq!(response.body_as_string().unwrap(), "denied");
+    }
+
+    #[test]
+    fn test_middleware_order_reflects_priority_not_registration_order() {
+        let test_server = test_server();
+        let server = test_server.server();
+
+        // 故意乱序注册：40 在前、10 在后，执行顺序应按优先级而非注册顺序排列
+        server.add_middleware_with_priority("second".to_string(), 40, |ctx, next| next(ctx)).unwrap();
+        server.add_middleware_with_priority("first".to_string(), 10, |ctx, next| next(ctx)).unwrap();
+        server.add_route(
+            HttpMethod::GET,
+            "/ordered",
+            RequestHandler::new(|_| Ok(ResponseContext::with_text(HttpStatus::Ok, "ok"))),
+        ).unwrap();
+
+        let response = test_server.call(TestRequest::get("/ordered")).unwrap();
+        assert_eq!(response.status.as_u16(), 200);
+
+        let order = server.middleware_order();
+        let first_pos = order.iter().position(|name| name == "first").unwrap();
+        let second_pos = order.iter().position(|name| name == "second").unwrap();
+        assert!(first_pos < second_pos, "middleware with lower priority should run first: {:?}", order);
+    }
+
+    #[test]
+    fn test_scoped_middleware_only_runs_under_its_prefix() {
+        let test_server = test_server();
+        test_server.server().add_scoped_middleware("/api/admin", "admin_guard".to_string(), |_ctx, _next| {
+            Ok(MiddlewareResult::Response(
+                ResponseContext::with_text(HttpStatus::Forbidden, "admins only"),
+            ))
+        }).unwrap();
+        test_server.server().add_route(
+            HttpMethod::GET,
+            "/api/admin/users",
+            RequestHandler::new(|_| Ok(ResponseContext::with_text(HttpStatus::Ok, "admin page"))),
+        ).unwrap();
+        test_server.server().add_route(
+            HttpMethod::GET,
+            "/public",
+            RequestHandler::new(|_| Ok(ResponseContext::with_text(HttpStatus::Ok, "public page"))),
+        ).unwrap();
+
+        let admin_response = test_server.call(TestRequest::get("/api/admin/users")).unwrap();
+        assert_eq!(admin_response.status.as_u16(), 403);
+
+        let public_response = test_server.call(TestRequest::get("/public")).unwrap();
+        assert_eq!(public_response.status.as_u16(), 200);
+        assert_eq!(public_response.body_as_string().unwrap(), "public page");
+
+        assert_eq!(test_server.server().scoped_middleware_count(), 1);
+    }
+
+    #[test]
+    fn test_error_handler_transforms_matching_status_response() {
+        let test_server = test_server();
+        test_server.server().add_error_handler(HttpStatus::Forbidden, |_response| {
+            ResponseContext::with_json(HttpStatus::Forbidden, r#"{"error": "forbidden"}"#)
+        }).unwrap();
+        test_server.server().add_middleware("deny_all".to_string(), |_ctx, _next| {
+            Ok(MiddlewareResult::Response(
+                ResponseContext::with_text(HttpStatus::Forbidden, "denied"),
+            ))
+        }).unwrap();
+        test_server.server().add_route(
+            HttpMethod::GET,
+            "/secret",
+            RequestHandler::new(|_| Ok(ResponseContext::with_text(HttpStatus::Ok, "should not run"))),
+        ).unwrap();
+
+        let response = test_server.call(TestRequest::get("/secret")).unwrap();
+        assert_eq!(response.status.as_u16(), 403);
+        assert_eq!(response.body_as_string().unwrap(), r#"{"error": "forbidden"}"#);
+    }
+
+    #[test]
+    fn test_session_middleware_round_trips_through_full_pipeline() {
+        use crate::middleware::session::InMemorySessionStore;
+        use std::sync::Arc;
+
+        let test_server = test_server();
+        let store = Arc::new(InMemorySessionStore::new());
+        test_server.server().add_session_middleware(store).unwrap();
+        test_server.server().add_middleware("greeter".to_string(), |ctx, next| {
+            ctx.request.set_user_data("session.visits".to_string(), "1".to_string());
+            next(ctx)
+        }).unwrap();
+        test_server.server().add_route(
+            HttpMethod::GET,
+            "/dashboard",
+            RequestHandler::new(|context| {
+                let visits = context.get_user_data("session.visits").cloned().unwrap_or_default();
+                Ok(ResponseContext::with_text(HttpStatus::Ok, &format!("visits: {}", visits)))
+            }),
+        ).unwrap();
+
+        let response = test_server.call(TestRequest::get("/dashboard")).unwrap();
+        assert_eq!(response.body_as_string().unwrap(), "visits: 1");
+        assert!(response.headers.get("Set-Cookie").unwrap().starts_with("session_id="));
+    }
+
+    #[test]
+    fn test_session_handle_and_custom_cookie_name_round_trip_through_full_pipeline() {
+        use crate::middleware::session::InMemorySessionStore;
+        use std::sync::Arc;
+
+        let test_server = test_server();
+        let store = Arc::new(InMemorySessionStore::new());
+        test_server.server().add_session_middleware_with_cookie_name(store, "sid").unwrap();
+        test_server.server().add_middleware("greeter".to_string(), |ctx, next| {
+            ctx.session().set("visits", "1");
+            next(ctx)
+        }).unwrap();
+        test_server.server().add_route(
+            HttpMethod::GET,
+            "/dashboard",
+            RequestHandler::new(|context| {
+                let visits = context.get_user_data("session.visits").cloned().unwrap_or_default();
+                Ok(ResponseContext::with_text(HttpStatus::Ok, &format!("visits: {}", visits)))
+            }),
+        ).unwrap();
+
+        let response = test_server.call(TestRequest::get("/dashboard")).unwrap();
+        assert_eq!(response.body_as_string().unwrap(), "visits: 1");
+        assert!(response.headers.get("Set-Cookie").unwrap().starts_with("sid="));
+    }
+
+    #[test]
+    fn test_route_handler_returning_template_renders_through_pipeline() {
+        use crate::core::types::TemplateValue;
+
+        let dir = std::env::temp_dir().join(format!("hush_testing_templates_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("welcome.html"), "Hello, {{name}}!").unwrap();
+
+        let test_server = test_server_with_templates(&dir);
+        test_server.server().add_route(
+            HttpMethod::GET,
+            "/welcome",
+            RequestHandler::new(|_| {
+                let mut ctx = HashMap::new();
+                ctx.insert("name".to_string(), TemplateValue::Text("Ada".to_string()));
+                Ok(ResponseContext::with_template("welcome.html", ctx))
+            }),
+        ).unwrap();
+
+        let response = test_server.call(TestRequest::get("/welcome")).unwrap();
+        assert_eq!(response.status.as_u16(), 200);
+        assert_eq!(response.body_as_string().unwrap(), "Hello, Ada!");
+        assert_eq!(response.headers.get("Content-Type"), Some(&"text/html".to_string()));
+    }
+}